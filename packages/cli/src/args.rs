@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 
 #[derive(Debug, Parser)]
@@ -10,12 +10,106 @@ pub struct Args {
 
 #[derive(Debug, Subcommand)]
 pub enum Commands {
-    Run(RunArgs)
+    Run(RunArgs),
+    /// Render every page into a directory for static hosting
+    Build(BuildArgs),
+    /// Validate pages without serving them
+    Check(CheckArgs),
+    /// Create a new project
+    New(NewArgs),
+    /// Show what the pipeline produces for a page
+    Emit(EmitArgs),
+    /// Evaluate FlexScript interactively
+    Repl
 }
 
 #[derive(Debug, Parser)]
 pub struct RunArgs {
-    pub path: String,
+    /// Page file, directory of pages or inline code. Defaults to pages in
+    /// monolith.toml
+    pub path: Option<String>,
     #[clap(short, long, default_value = "false")]
-    pub watch: bool
-}
\ No newline at end of file
+    pub watch: bool,
+    /// Defaults to server.host in monolith.toml or 127.0.0.1
+    #[clap(long)]
+    pub host: Option<String>,
+    /// Defaults to server.port in monolith.toml or 8080
+    #[clap(short, long)]
+    pub port: Option<u16>,
+    /// Listen on a unix domain socket instead of host and port
+    #[clap(long)]
+    pub unix_socket: Option<String>,
+    /// Config file, defaults to monolith.toml in the app directory
+    #[clap(long)]
+    pub config: Option<String>,
+    /// Config profile, defaults to dev in watch mode and prod otherwise
+    #[clap(long)]
+    pub profile: Option<String>
+}
+
+#[derive(Debug, Parser)]
+pub struct BuildArgs {
+    pub path: Option<String>,
+    /// Directory the pages are written to
+    #[clap(short, long, default_value = "dist")]
+    pub out: String,
+    /// Config file, defaults to monolith.toml in the app directory
+    #[clap(long)]
+    pub config: Option<String>,
+    /// Config profile, defaults to prod
+    #[clap(long)]
+    pub profile: Option<String>
+}
+
+#[derive(Debug, Parser)]
+pub struct CheckArgs {
+    /// Page file or directory of pages
+    pub path: String
+}
+
+#[derive(Debug, Parser)]
+pub struct NewArgs {
+    /// Directory to create the project in
+    pub path: String,
+    #[clap(short, long, value_enum, default_value_t = Template::Blank)]
+    pub template: Template
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Template {
+    /// A single welcome page
+    Blank,
+    /// The todo example
+    Todo,
+    /// Items kept with `Store` that can be added, edited and deleted
+    Crud
+}
+
+#[derive(Debug, Parser)]
+pub struct EmitArgs {
+    /// Page file or inline code
+    pub path: String,
+    /// Stages to show, all of them by default
+    #[clap(short, long, value_enum)]
+    pub stage: Vec<Stage>,
+    #[clap(short, long, value_enum, default_value_t = Format::Pretty)]
+    pub format: Format
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Stage {
+    /// FlexScript syntax tree
+    Ast,
+    /// JavaScript syntax tree
+    JsNode,
+    /// Generated JavaScript
+    Js,
+    /// Rendered page
+    Html
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    Pretty,
+    Json
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::pages;
+
+/// Checks a page file or every `.fs` file under a directory and prints
+/// the problems found. Returns how many there were.
+pub fn check(path: &Path) -> anyhow::Result<usize> {
+    let mut files = vec![];
+
+    match path.is_dir() {
+        true => pages::collect(path, &mut files)?,
+        false => files.push(path.to_path_buf())
+    }
+
+    let count = check_files(&files)?;
+
+    println!("checked {} files, {} problems", files.len(), count);
+
+    Ok(count)
+}
+
+fn check_files(files: &[PathBuf]) -> anyhow::Result<usize> {
+    let mut count = 0;
+
+    for file in files {
+        let code = std::fs::read_to_string(file)?;
+
+        for diagnostic in monolith_core::check(&code) {
+            println!("{}:{}", file.display(), diagnostic);
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
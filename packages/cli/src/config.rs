@@ -0,0 +1,387 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use log::LevelFilter;
+use monolith_core::Method;
+use monolith_core::Monolith;
+use serde::Deserialize;
+
+pub const CONFIG_FILE: &str = "monolith.toml";
+
+/// Contents of `monolith.toml`. Every key can be repeated in a
+/// `[profile.<name>]` table to override it for that profile.
+///
+/// ```toml
+/// pages = "pages"
+/// log_level = "info"
+///
+/// [server]
+/// port = 8080
+///
+/// [[static]]
+/// prefix = "/assets"
+/// dir = "public"
+///
+/// [database]
+/// url = "postgres://localhost/app"
+///
+/// [profile.prod]
+/// log_level = "warn"
+/// server = { host = "0.0.0.0", port = 80 }
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    pages: Option<PathBuf>,
+    log_level: Option<String>,
+    dev: Option<bool>,
+    #[serde(default)]
+    server: ServerConfig,
+    #[serde(rename = "static")]
+    statics: Option<Vec<StaticConfig>>,
+    routes: Option<Vec<RouteConfig>>,
+    database: Option<DatabaseConfig>,
+    #[serde(default)]
+    settings: BTreeMap<String, String>,
+    #[serde(default)]
+    profile: BTreeMap<String, ConfigFile>
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ServerConfig {
+    host: Option<String>,
+    port: Option<u16>,
+    unix_socket: Option<PathBuf>,
+    workers: Option<usize>,
+    /// Seconds
+    shutdown_timeout: Option<u64>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StaticConfig {
+    prefix: String,
+    dir: PathBuf
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RouteConfig {
+    path: String,
+    file: PathBuf,
+    method: Option<String>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DatabaseConfig {
+    url: String
+}
+
+impl ConfigFile {
+    /// Values set in `profile` win over the ones in `self`.
+    fn merge(self, profile: ConfigFile) -> ConfigFile {
+        let mut settings = self.settings;
+        settings.extend(profile.settings);
+
+        ConfigFile {
+            pages: profile.pages.or(self.pages),
+            log_level: profile.log_level.or(self.log_level),
+            dev: profile.dev.or(self.dev),
+            server: ServerConfig {
+                host: profile.server.host.or(self.server.host),
+                port: profile.server.port.or(self.server.port),
+                unix_socket: profile.server.unix_socket.or(self.server.unix_socket),
+                workers: profile.server.workers.or(self.server.workers),
+                shutdown_timeout: profile.server.shutdown_timeout.or(self.server.shutdown_timeout)
+            },
+            statics: profile.statics.or(self.statics),
+            routes: profile.routes.or(self.routes),
+            database: profile.database.or(self.database),
+            settings: settings,
+            profile: BTreeMap::new()
+        }
+    }
+}
+
+/// Settings for serving and building an app. Comes from `monolith.toml`,
+/// the selected profile and `MONOLITH_*` environment variables, in
+/// increasing priority. Command line flags are applied on top by the
+/// caller.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub profile: String,
+    pub pages: Option<PathBuf>,
+    pub log_level: LevelFilter,
+    pub dev: Option<bool>,
+    pub host: String,
+    pub port: u16,
+    pub unix_socket: Option<PathBuf>,
+    pub workers: Option<usize>,
+    pub shutdown_timeout: Option<Duration>,
+    statics: Vec<(String, PathBuf)>,
+    routes: Vec<(Method, String, PathBuf)>,
+    settings: HashMap<String, String>
+}
+
+impl Config {
+    /// Loads `file`, or only the defaults and environment when there is no
+    /// config file.
+    pub fn load(file: Option<&Path>, profile: &str) -> anyhow::Result<Self> {
+        let env = |name: &str| std::env::var(name).ok();
+
+        let file = match file {
+            Some(file) => file,
+            None => return Self::parse("", Path::new("."), profile, env)
+        };
+
+        let text = std::fs::read_to_string(file)
+            .map_err(|err| anyhow::anyhow!("{}: {}", file.display(), err))?;
+
+        let root = file.parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        Self::parse(&text, root, profile, env)
+            .map_err(|err| anyhow::anyhow!("{}: {}", file.display(), err))
+    }
+
+    /// Config file next to the pages: in the app directory, or else in the
+    /// working directory.
+    pub fn find(path: Option<&str>) -> Option<PathBuf> {
+        let dir = path.map(Path::new).filter(|p| p.is_dir());
+
+        dir.map(|dir| dir.join(CONFIG_FILE))
+            .filter(|file| file.is_file())
+            .or_else(|| Some(PathBuf::from(CONFIG_FILE)).filter(|file| file.is_file()))
+    }
+
+    fn parse(
+        text: &str,
+        root: &Path,
+        profile: &str,
+        env: impl Fn(&str) -> Option<String>
+    ) -> anyhow::Result<Self> {
+        let mut file = toml::from_str::<ConfigFile>(text)?;
+
+        for (name, p) in &file.profile {
+            if !p.profile.is_empty() {
+                anyhow::bail!("profile.{}: profiles can not be nested", name);
+            }
+        }
+
+        let file = match file.profile.remove(profile) {
+            Some(p) => file.merge(p),
+            None if profile == "dev" || profile == "prod" => file,
+            None => {
+                let defined = file.profile.keys()
+                    .map(|k| k.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                anyhow::bail!("unknown profile {:?}, defined profiles: {}", profile, defined);
+            }
+        };
+
+        let log_level = env("MONOLITH_LOG_LEVEL").or(file.log_level).unwrap_or("info".to_string());
+        let log_level = LevelFilter::from_str(&log_level)
+            .map_err(|_| anyhow::anyhow!(
+                "log_level: expected one of off, error, warn, info, debug or trace, got {:?}",
+                log_level
+            ))?;
+
+        let port = match env("MONOLITH_PORT") {
+            Some(port) => port.parse::<u16>()
+                .map_err(|_| anyhow::anyhow!("MONOLITH_PORT: invalid port {:?}", port))?,
+            None => file.server.port.unwrap_or(8080)
+        };
+
+        let workers = match env("MONOLITH_WORKERS") {
+            Some(workers) => Some(workers.parse::<usize>()
+                .map_err(|_| anyhow::anyhow!("MONOLITH_WORKERS: invalid number {:?}", workers))?),
+            None => file.server.workers
+        };
+
+        if workers == Some(0) {
+            anyhow::bail!("server.workers must be at least 1");
+        }
+
+        let mut statics = vec![];
+
+        for s in file.statics.unwrap_or_default() {
+            let dir = root.join(&s.dir);
+
+            if !s.prefix.starts_with('/') {
+                anyhow::bail!("static prefix {:?} must start with /", s.prefix);
+            }
+
+            if !dir.is_dir() {
+                anyhow::bail!("static {}: {} is not a directory", s.prefix, dir.display());
+            }
+
+            statics.push((s.prefix, dir));
+        }
+
+        let mut routes = vec![];
+
+        for r in file.routes.unwrap_or_default() {
+            let method = r.method.as_deref().unwrap_or("GET").to_uppercase();
+            let method = Method::from_bytes(method.as_bytes())
+                .map_err(|_| anyhow::anyhow!("route {}: invalid method {:?}", r.path, method))?;
+            let file = root.join(&r.file);
+
+            if !r.path.starts_with('/') {
+                anyhow::bail!("route path {:?} must start with /", r.path);
+            }
+
+            if !file.is_file() {
+                anyhow::bail!("route {}: {} does not exist", r.path, file.display());
+            }
+
+            routes.push((method, r.path, file));
+        }
+
+        let database_url = env("MONOLITH_DATABASE_URL").or(file.database.map(|d| d.url));
+
+        let mut settings = file.settings.into_iter().collect::<HashMap<_, _>>();
+
+        if let Some(url) = database_url {
+            if !url.contains("://") {
+                anyhow::bail!("database.url: expected a url like postgres://user@host/db, got {:?}", url);
+            }
+
+            settings.insert("database_url".to_string(), url);
+        }
+
+        Ok(Self {
+            profile: profile.to_string(),
+            pages: env("MONOLITH_PAGES").map(PathBuf::from).or(file.pages.map(|p| root.join(p))),
+            log_level: log_level,
+            dev: file.dev,
+            host: env("MONOLITH_HOST").or(file.server.host).unwrap_or("127.0.0.1".to_string()),
+            port: port,
+            unix_socket: env("MONOLITH_UNIX_SOCKET").map(PathBuf::from)
+                .or(file.server.unix_socket.map(|p| root.join(p))),
+            workers: workers,
+            shutdown_timeout: file.server.shutdown_timeout.map(Duration::from_secs),
+            statics: statics,
+            routes: routes,
+            settings: settings
+        })
+    }
+
+    /// Files of the routes configured in addition to the pages.
+    pub fn route_files(&self) -> Vec<PathBuf> {
+        self.routes.iter()
+            .map(|(_, _, file)| file.clone())
+            .collect()
+    }
+
+    /// Adds the configured routes, static directories and settings.
+    pub fn apply(&self, mut monolith: Monolith) -> anyhow::Result<Monolith> {
+        for (method, path, file) in &self.routes {
+            let code = std::fs::read_to_string(file)?;
+            monolith = monolith.route(method.clone(), path, &code);
+        }
+
+        for (prefix, dir) in &self.statics {
+            monolith = monolith.static_dir(prefix, dir);
+        }
+
+        for (name, value) in &self.settings {
+            monolith = monolith.setting(name, value);
+        }
+
+        if let Some(workers) = self.workers {
+            monolith = monolith.workers(workers);
+        }
+
+        if let Some(timeout) = self.shutdown_timeout {
+            monolith = monolith.shutdown_timeout(timeout);
+        }
+
+        Ok(monolith)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    const CONFIG: &str = r#"
+        log_level = "debug"
+
+        [server]
+        port = 3000
+
+        [database]
+        url = "postgres://localhost/dev"
+
+        [profile.prod]
+        log_level = "warn"
+        server = { host = "0.0.0.0" }
+        database = { url = "postgres://db/prod" }
+    "#;
+
+    #[test]
+    fn profile_overrides_base() {
+        let dev = Config::parse(CONFIG, Path::new("."), "dev", no_env).unwrap();
+
+        assert_eq!(dev.log_level, LevelFilter::Debug);
+        assert_eq!(dev.host, "127.0.0.1");
+        assert_eq!(dev.port, 3000);
+        assert_eq!(dev.settings["database_url"], "postgres://localhost/dev");
+
+        let prod = Config::parse(CONFIG, Path::new("."), "prod", no_env).unwrap();
+
+        assert_eq!(prod.log_level, LevelFilter::Warn);
+        assert_eq!(prod.host, "0.0.0.0");
+        assert_eq!(prod.port, 3000);
+        assert_eq!(prod.settings["database_url"], "postgres://db/prod");
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let env = |name: &str| match name {
+            "MONOLITH_PORT" => Some("9000".to_string()),
+            "MONOLITH_LOG_LEVEL" => Some("trace".to_string()),
+            _ => None
+        };
+
+        let config = Config::parse(CONFIG, Path::new("."), "prod", env).unwrap();
+
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.log_level, LevelFilter::Trace);
+    }
+
+    #[test]
+    fn reports_invalid_config() {
+        let err = |text: &str, profile: &str| {
+            Config::parse(text, Path::new("."), profile, no_env).unwrap_err().to_string()
+        };
+
+        assert!(err("[server]\nport = \"80\"", "dev").contains("line 2"));
+        assert!(err("prot = 80", "dev").contains("unknown field `prot`"));
+        assert_eq!(
+            err("log_level = \"loud\"", "dev"),
+            r#"log_level: expected one of off, error, warn, info, debug or trace, got "loud""#
+        );
+        assert_eq!(
+            err(CONFIG, "staging"),
+            r#"unknown profile "staging", defined profiles: prod"#
+        );
+        assert_eq!(
+            err("[database]\nurl = \"localhost\"", "dev"),
+            r#"database.url: expected a url like postgres://user@host/db, got "localhost""#
+        );
+    }
+}
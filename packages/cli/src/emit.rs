@@ -0,0 +1,52 @@
+use monolith_core::EmitStage;
+
+use crate::args::Format;
+use crate::args::Stage;
+
+/// Prints the selected stages for the page at `path`, which can also be
+/// inline code. Several stages are printed one after another, or as one
+/// json object keyed by stage.
+pub async fn emit(path: &str, stages: &[Stage], format: Format) -> anyhow::Result<()> {
+    let code = match std::path::Path::new(path).is_file() {
+        true => std::fs::read_to_string(path)?,
+        false => path.to_string()
+    };
+
+    let stages = match stages.is_empty() {
+        true => &[Stage::Ast, Stage::JsNode, Stage::Js, Stage::Html][..],
+        false => stages
+    };
+
+    let mut json = serde_json::Map::new();
+
+    for stage in stages {
+        let (name, emit_stage) = match stage {
+            Stage::Ast => ("ast", EmitStage::Ast),
+            Stage::JsNode => ("js-node", EmitStage::JsNode),
+            Stage::Js => ("js", EmitStage::Js),
+            Stage::Html => ("html", EmitStage::Html)
+        };
+
+        let emitted = monolith_core::emit(&code, emit_stage).await
+            .map_err(|err| anyhow::anyhow!("{}: {}", name, err))?;
+
+        match format {
+            Format::Pretty if stages.len() == 1 => println!("{}", emitted.pretty),
+            Format::Pretty => println!("== {} ==\n{}\n", name, emitted.pretty),
+            Format::Json => {
+                json.insert(name.to_string(), emitted.json);
+            }
+        }
+    }
+
+    if format == Format::Json {
+        let json = match json.len() {
+            1 => json.into_iter().next().unwrap().1,
+            _ => serde_json::Value::Object(json)
+        };
+
+        println!("{}", serde_json::to_string_pretty(&json)?);
+    }
+
+    Ok(())
+}
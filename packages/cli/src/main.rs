@@ -1,38 +1,247 @@
 use std::path::Path;
+use std::path::PathBuf;
 
 use args::{Args, Commands};
 use clap::Parser;
+use config::Config;
 use log::LevelFilter;
 use monolith_core::Monolith;
 use simple_logger::SimpleLogger;
 
 mod args;
+mod check;
+mod config;
+mod emit;
+mod new;
+mod pages;
+mod repl;
+mod watch;
 
 #[tokio::main]
 async fn main() {
+    // Lowered or raised once the config is loaded.
     SimpleLogger::new()
-        .with_level(LevelFilter::Info)
+        .with_level(LevelFilter::Trace)
         .without_timestamps()
         .init()
         .unwrap();
 
+    log::set_max_level(LevelFilter::Info);
+
     let args = Args::parse();
 
     match args.command {
         Commands::Run(args) => {
-            if args.watch {
+            let default_profile = match args.watch {
+                true => "dev",
+                false => "prod"
+            };
+            let config = load_config(
+                args.config.as_deref(),
+                args.path.as_deref(),
+                args.profile.as_deref(),
+                default_profile
+            );
+            let pages = pages_path(args.path, &config);
+
+            let path = Path::new(&pages);
+            let watching = args.watch && path.exists();
+
+            if args.watch && !watching {
+                log::warn!("watch mode needs a file or directory, not inline code");
+            }
+
+            let build = || config.apply(load(&pages)?);
+
+            let monolith = match build() {
+                Ok(monolith) => monolith,
+                Err(err) => {
+                    log::error!("failed to load {}: {}", pages, err);
+                    std::process::exit(1);
+                }
+            };
+
+            let mut monolith = monolith
+                .host(args.host.as_ref().unwrap_or(&config.host))
+                .listen(args.port.unwrap_or(config.port))
+                .dev(config.dev.unwrap_or(watching));
+
+            #[cfg(unix)]
+            if let Some(path) = args.unix_socket.map(PathBuf::from).or(config.unix_socket.clone()) {
+                monolith = monolith.unix_socket(path);
+            }
+
+            let server = match monolith.bind().await {
+                Ok(server) => server,
+                Err(err) => {
+                    log::error!("failed to listen: {}", err);
+                    std::process::exit(1);
+                }
+            };
+
+            println!("listening on {}", server.local_addr());
+
+            if watching {
                 println!("running in watch mode");
+
+                let watcher = watch::watch(path.to_path_buf(), config.route_files(), server.reloader(), build);
+
+                tokio::select! {
+                    _ = server.run_until(shutdown_signal()) => {},
+                    _ = watcher => {}
+                }
+            } else {
+                server.run_until(shutdown_signal()).await;
             }
+        },
+        Commands::Build(args) => {
+            let config = load_config(
+                args.config.as_deref(),
+                args.path.as_deref(),
+                args.profile.as_deref(),
+                "prod"
+            );
+            let pages = pages_path(args.path, &config);
 
-            let code = match Path::new(&args.path).exists() {
-                true => std::fs::read_to_string(&args.path).unwrap(),
-                false => args.path.clone()
+            let monolith = match load(&pages).and_then(|m| config.apply(m)) {
+                Ok(monolith) => monolith,
+                Err(err) => {
+                    log::error!("failed to load {}: {}", pages, err);
+                    std::process::exit(1);
+                }
             };
 
-            Monolith::new()
-                .add("/", &code)
-                .listen(8080)
-                .start().await;
+            match monolith.export(&args.out).await {
+                Ok(files) => {
+                    for file in &files {
+                        println!("{}", file.display());
+                    }
+
+                    println!("exported {} pages to {}", files.len(), args.out);
+                },
+                Err(err) => {
+                    log::error!("export failed: {}", err);
+                    std::process::exit(1);
+                }
+            }
+        },
+        Commands::New(args) => {
+            if let Err(err) = new::new(Path::new(&args.path), args.template) {
+                log::error!("failed to create {}: {}", args.path, err);
+                std::process::exit(1);
+            }
+
+            println!("created {}, start it with: monolith run {} --watch", args.path, args.path);
+        },
+        Commands::Emit(args) => {
+            // Failures are reported through the result, logs would only
+            // mix into the output.
+            log::set_max_level(LevelFilter::Off);
+
+            if let Err(err) = emit::emit(&args.path, &args.stage, args.format).await {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        Commands::Repl => {
+            log::set_max_level(LevelFilter::Warn);
+
+            if let Err(err) = repl::repl().await {
+                log::error!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        Commands::Check(args) => {
+            match check::check(Path::new(&args.path)) {
+                Ok(0) => {},
+                Ok(_) => std::process::exit(1),
+                Err(err) => {
+                    log::error!("failed to check {}: {}", args.path, err);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// Loads `monolith.toml` and applies its log level. The profile comes from
+/// the command line, `MONOLITH_PROFILE` or else `default_profile`.
+fn load_config(
+    file: Option<&str>,
+    path: Option<&str>,
+    profile: Option<&str>,
+    default_profile: &str
+) -> Config {
+    let profile = profile.map(|p| p.to_string())
+        .or(std::env::var("MONOLITH_PROFILE").ok())
+        .unwrap_or(default_profile.to_string());
+
+    let file = file.map(PathBuf::from).or_else(|| Config::find(path));
+
+    match Config::load(file.as_deref(), &profile) {
+        Ok(config) => {
+            log::set_max_level(config.log_level);
+
+            if let Some(file) = &file {
+                log::info!("loaded {} with the {} profile", file.display(), config.profile);
+            }
+
+            config
+        },
+        Err(err) => {
+            log::error!("invalid config: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Pages given on the command line, or else the ones in the config.
+fn pages_path(path: Option<String>, config: &Config) -> String {
+    let pages = path.or(config.pages.as_ref().map(|p| p.display().to_string()));
+
+    match pages {
+        Some(pages) => pages,
+        None => {
+            log::error!("no pages given, pass a path or set pages in {}", config::CONFIG_FILE);
+            std::process::exit(1);
         }
     }
 }
+
+/// Loads a directory of pages, a single page file or inline code.
+fn load(path: &str) -> anyhow::Result<Monolith> {
+    let file = Path::new(path);
+
+    if file.is_dir() {
+        return pages::load_dir(file);
+    }
+
+    let code = match file.exists() {
+        true => std::fs::read_to_string(file)?,
+        false => path.to_string()
+    };
+
+    Ok(Monolith::new().add("/", &code))
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.unwrap();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .unwrap()
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => log::info!("received SIGINT"),
+        _ = terminate => log::info!("received SIGTERM")
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use crate::args::Template;
+
+type Files = &'static [(&'static str, &'static str)];
+
+/// Files every project gets. Template files with the same path replace
+/// them.
+const COMMON: Files = &[
+    ("monolith.toml", include_str!("../templates/common/monolith.toml")),
+    ("schema.sql", include_str!("../templates/common/schema.sql")),
+    ("pages/_layout.fs", include_str!("../templates/common/pages/_layout.fs")),
+    ("pages/404.fs", include_str!("../templates/common/pages/404.fs")),
+    ("public/robots.txt", include_str!("../templates/common/public/robots.txt"))
+];
+
+const BLANK: Files = &[
+    ("pages/index.fs", include_str!("../templates/blank/pages/index.fs"))
+];
+
+const TODO: Files = &[
+    ("pages/index.fs", include_str!("../../../examples/todo.fs"))
+];
+
+const CRUD: Files = &[
+    ("monolith.toml", include_str!("../templates/crud/monolith.toml")),
+    ("pages/index.fs", include_str!("../templates/crud/pages/index.fs")),
+    ("actions/create_item.fs", include_str!("../templates/crud/actions/create_item.fs")),
+    ("actions/update_item.fs", include_str!("../templates/crud/actions/update_item.fs")),
+    ("actions/delete_item.fs", include_str!("../templates/crud/actions/delete_item.fs"))
+];
+
+/// Creates a project from `template` in directory `dir`, which must not
+/// exist yet or be empty. `{{name}}` in the templates is replaced with the
+/// directory name.
+pub fn new(dir: &Path, template: Template) -> anyhow::Result<()> {
+    if dir.exists() && std::fs::read_dir(dir)?.next().is_some() {
+        anyhow::bail!("{} already exists and is not empty", dir.display());
+    }
+
+    let name = dir.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or("app".to_string());
+
+    let files = match template {
+        Template::Blank => BLANK,
+        Template::Todo => TODO,
+        Template::Crud => CRUD
+    };
+
+    let common = COMMON.iter()
+        .filter(|(path, _)| !files.iter().any(|(p, _)| p == path));
+
+    for (path, content) in common.chain(files.iter()) {
+        let file = dir.join(path);
+
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&file, content.replace("{{name}}", &name))?;
+        log::info!("created {}", file.display());
+    }
+
+    Ok(())
+}
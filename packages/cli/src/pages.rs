@@ -0,0 +1,114 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use monolith_core::Monolith;
+
+/// Builds an app from a directory of `.fs` files. Pages are read from the
+/// `pages` subdirectory when there is one.
+///
+/// - `index.fs` is served at the path of its directory
+/// - `[id].fs` and `[id]` directories become `:id` route parameters
+/// - `_layout.fs` wraps every page in its directory and below
+/// - `404.fs` in the root is shown when no page matches
+/// - other files starting with `_` are not served
+pub fn load_dir(dir: &Path) -> anyhow::Result<Monolith> {
+    let pages = dir.join("pages");
+    let root = match pages.is_dir() {
+        true => pages,
+        false => dir.to_path_buf()
+    };
+
+    let mut files = vec![];
+    collect(&root, &mut files)?;
+
+    let mut routes = vec![];
+    let mut monolith = Monolith::new();
+
+    for file in files {
+        let rel = file.strip_prefix(&root)?;
+        let code = std::fs::read_to_string(&file)?;
+
+        let stem = rel.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+
+        let dir_path = match rel.parent() {
+            Some(parent) => route_path(parent),
+            None => "/".to_string()
+        };
+
+        match stem {
+            "404" if dir_path == "/" => monolith = monolith.not_found(&code),
+            "_layout" => monolith = monolith.layout(&dir_path, &code),
+            _ if stem.starts_with('_') => {},
+            _ => routes.push((route_path(rel), code))
+        }
+    }
+
+    // Static segments win over parameters, so /todo/new is matched before
+    // /todo/:id.
+    routes.sort_by_key(|(path, _)| {
+        (path.split('/').filter(|s| s.starts_with(':')).count(), path.clone())
+    });
+
+    for (path, code) in routes {
+        log::info!("route {}", path);
+        monolith = monolith.add(&path, &code);
+    }
+
+    Ok(monolith)
+}
+
+/// Collects the `.fs` files under `dir`, sorted by path.
+pub fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect(&path, files)?;
+        } else if path.extension().map(|e| e == "fs").unwrap_or(false) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Route for a page file relative to the pages directory, for example
+/// `todo/[id].fs` becomes `/todo/:id`.
+fn route_path(rel: &Path) -> String {
+    let segments = rel.with_extension("")
+        .iter()
+        .filter_map(|s| s.to_str())
+        .map(|s| match s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(param) => format!(":{}", param),
+            None => s.to_string()
+        })
+        .collect::<Vec<_>>();
+
+    let segments = match segments.last().map(|s| s.as_str()) {
+        Some("index") => &segments[..segments.len() - 1],
+        _ => &segments[..]
+    };
+
+    format!("/{}", segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_files_to_routes() {
+        assert_eq!(route_path(Path::new("index.fs")), "/");
+        assert_eq!(route_path(Path::new("about.fs")), "/about");
+        assert_eq!(route_path(Path::new("todo/index.fs")), "/todo");
+        assert_eq!(route_path(Path::new("todo/[id].fs")), "/todo/:id");
+        assert_eq!(route_path(Path::new("todo/[id]/edit.fs")), "/todo/:id/edit");
+        assert_eq!(route_path(Path::new("")), "/");
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::Write;
+
+use monolith_core::Repl;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::BufReader;
+
+const HELP: &str = "Enter FlexScript to evaluate it. Returned Html and elements are shown as html
+together with the JavaScript generated for the snippet.
+
+  :js     toggle showing the generated JavaScript
+  :reset  forget all variables
+  :quit   exit";
+
+/// Reads snippets from stdin until `:quit` or end of input. A snippet
+/// continues over several lines while brackets are left open.
+pub async fn repl() -> anyhow::Result<()> {
+    println!("monolith repl, :help for help");
+
+    let mut repl = Repl::new();
+    let mut show_js = true;
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut snippet = String::new();
+
+    loop {
+        print!("{}", if snippet.is_empty() { "> " } else { ". " });
+        std::io::stdout().flush()?;
+
+        let line = match lines.next_line().await? {
+            Some(line) => line,
+            None => break
+        };
+
+        if snippet.is_empty() {
+            match line.trim() {
+                "" => continue,
+                ":quit" | ":q" => break,
+                ":help" => {
+                    println!("{}", HELP);
+                    continue;
+                },
+                ":reset" => {
+                    repl.reset();
+                    continue;
+                },
+                ":js" => {
+                    show_js = !show_js;
+                    println!("showing js: {}", show_js);
+                    continue;
+                },
+                _ => {}
+            }
+        }
+
+        snippet.push_str(&line);
+        snippet.push('\n');
+
+        if open_brackets(&snippet) > 0 {
+            continue;
+        }
+
+        match repl.eval(&snippet).await {
+            Ok(evaluation) => {
+                if let Some(output) = evaluation.output {
+                    println!("{}", output);
+                }
+
+                if show_js {
+                    match evaluation.js {
+                        Ok(js) if js.trim().is_empty() => {},
+                        Ok(js) => println!("// js\n{}", js.trim_end()),
+                        Err(err) => println!("// js: not supported: {}", err)
+                    }
+                }
+            },
+            Err(err) => println!("error: {}", err)
+        }
+
+        snippet.clear();
+    }
+
+    Ok(())
+}
+
+/// Brackets opened but not closed yet, ignoring the ones in strings.
+fn open_brackets(code: &str) -> i32 {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in code.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' | '[' | '(' if !in_string => depth += 1,
+            '}' | ']' | ')' if !in_string => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_open_brackets() {
+        assert_eq!(open_brackets("H1 {"), 1);
+        assert_eq!(open_brackets("H1 { text: \"}\" }"), 0);
+        assert_eq!(open_brackets("[1, (2"), 2);
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use monolith_core::Monolith;
+use monolith_core::Reloader;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Directories that never hold pages, skipped so a project root can be
+/// watched without walking build output or dependencies.
+const IGNORED: &[&str] = &["target", "node_modules"];
+
+/// Watches the `.fs` files of the app and swaps the routes of the running
+/// server whenever one of them changes. `pages` is the pages directory,
+/// which is watched with its subdirectories, or a single page, for which
+/// the files next to it are watched as it may import them. `files` are
+/// other pages, like the routes of the config file.
+pub async fn watch<F>(pages: PathBuf, files: Vec<PathBuf>, reloader: Reloader, build: F)
+where
+    F: Fn() -> anyhow::Result<Monolith>
+{
+    log::info!("watching {}", pages.display());
+
+    let mut last = snapshot(&pages, &files);
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let current = snapshot(&pages, &files);
+
+        if current == last {
+            continue;
+        }
+
+        last = current;
+
+        let monolith = match build() {
+            Ok(monolith) => monolith,
+            Err(err) => {
+                log::error!("failed to load app: {}", err);
+                continue;
+            }
+        };
+
+        let errors = reloader.reload(monolith).await;
+
+        if errors.is_empty() {
+            log::info!("reloaded");
+        }
+
+        for err in errors {
+            log::error!("{}", err);
+        }
+    }
+}
+
+fn snapshot(pages: &Path, files: &[PathBuf]) -> BTreeMap<PathBuf, SystemTime> {
+    let mut snapshot = BTreeMap::new();
+
+    match pages.is_dir() {
+        true => collect(pages, usize::MAX, &mut snapshot),
+        false => {
+            let dir = pages.parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+
+            collect(dir, 1, &mut snapshot);
+        }
+    }
+
+    for file in files {
+        collect(file, 0, &mut snapshot);
+    }
+
+    snapshot
+}
+
+/// Adds `path` when it is a `.fs` file, or the `.fs` files in it down to
+/// `depth` levels when it is a directory.
+fn collect(path: &Path, depth: usize, files: &mut BTreeMap<PathBuf, SystemTime>) {
+    let meta = match std::fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => return
+    };
+
+    if meta.is_dir() {
+        if depth == 0 {
+            return;
+        }
+
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(_) => return
+        };
+
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            // Hidden directories like `.git` as well.
+            if name.starts_with('.') || IGNORED.contains(&name.as_str()) {
+                continue;
+            }
+
+            collect(&entry.path(), depth - 1, files);
+        }
+
+        return;
+    }
+
+    if path.extension().map(|e| e == "fs").unwrap_or(false) {
+        if let Ok(modified) = meta.modified() {
+            files.insert(path.to_path_buf(), modified);
+        }
+    }
+}
@@ -0,0 +1,10 @@
+return Html {
+    head: Head {
+        title: "{{name}}"
+    }
+    body: [
+        H2 {
+            text: "Welcome to {{name}}"
+        }
+    ]
+}
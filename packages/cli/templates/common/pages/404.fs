@@ -0,0 +1,10 @@
+return Html {
+    head: Head {
+        title: "Not found"
+    }
+    body: [
+        H2 {
+            text: "Page not found"
+        }
+    ]
+}
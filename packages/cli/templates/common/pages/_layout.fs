@@ -0,0 +1,17 @@
+return Html {
+    lang: "en"
+    head: Head {
+        title: "{{name}}"
+        viewport: "width=device-width, initial-scale=1"
+    }
+    body: [
+        H1 {
+            text: "{{name}}"
+        }
+        Div {
+            children: [
+                Outlet {}
+            ]
+        }
+    ]
+}
@@ -0,0 +1,2 @@
+-- Tables of {{name}}. Point database.url in monolith.toml at the database
+-- this schema is loaded into.
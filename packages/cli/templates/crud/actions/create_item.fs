@@ -0,0 +1,10 @@
+item = await Store {
+    collection: "items"
+    add: {
+        name: form.name
+    }
+}
+
+return Redirect {
+    to: "/"
+}
@@ -0,0 +1,9 @@
+item = await Store {
+    collection: "items"
+    id: form.id
+    remove: true
+}
+
+return Redirect {
+    to: "/"
+}
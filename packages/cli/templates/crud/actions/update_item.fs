@@ -0,0 +1,11 @@
+item = await Store {
+    collection: "items"
+    id: form.id
+    update: {
+        name: form.name
+    }
+}
+
+return Redirect {
+    to: "/"
+}
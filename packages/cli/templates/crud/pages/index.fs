@@ -0,0 +1,69 @@
+items = await Store {
+    collection: "items"
+}
+
+return Html {
+    head: Head {
+        title: "Items"
+    }
+    body: [
+        H2 {
+            text: "Items"
+        }
+        Div {
+            children: items.map((item) => {
+                return Div {
+                    style: {
+                        display: "flex"
+                    }
+                    children: [
+                        Form {
+                            action: "/items/update"
+                            children: [
+                                Input {
+                                    type: "hidden"
+                                    name: "id"
+                                    value: item.id
+                                }
+                                Input {
+                                    name: "name"
+                                    value: item.name
+                                    required: true
+                                }
+                                Button {
+                                    text: "Save"
+                                }
+                            ]
+                        }
+                        Form {
+                            action: "/items/delete"
+                            children: [
+                                Input {
+                                    type: "hidden"
+                                    name: "id"
+                                    value: item.id
+                                }
+                                Button {
+                                    text: "Delete"
+                                }
+                            ]
+                        }
+                    ]
+                }
+            })
+        }
+        Form {
+            action: "/items"
+            children: [
+                Input {
+                    name: "name"
+                    placeholder: "Name"
+                    required: true
+                }
+                Button {
+                    text: "Add"
+                }
+            ]
+        }
+    ]
+}
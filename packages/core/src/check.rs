@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::panic::catch_unwind;
+
+use flexscript::ASTNode;
+use flexscript::Parser;
+use flexscript::Value;
+
+use crate::errors::panic_message;
+use crate::html::CSSProps;
+use crate::html::HEAD_PROPS;
+use crate::html::Html;
+use crate::html::HtmlElType;
+use crate::html::RAW_HTML;
+use crate::js::JSGen;
+use crate::js::is_static_params;
+use crate::value;
+
+/// Problem found by `check`. Lines and columns start at 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub message: String
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// Elements that are not rendered themselves. `Outlet` marks where a
+/// layout puts the page.
+const SPECIAL_ELEMENTS: &[&str] = &["Outlet"];
+
+/// Validates page code without running it. The code is parsed, the page
+/// script is generated and the returned `Html` is converted with its
+/// dynamic parts left out. The `Html` checks are skipped for pages that do
+/// not return an `Html` literal.
+///
+/// FlexScript nodes carry no positions, so problems are located by
+/// searching the code for the offending name.
+pub fn check(code: &str) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        code: code,
+        offsets: HashMap::new(),
+        diagnostics: vec![]
+    };
+
+    checker.check();
+    checker.diagnostics
+}
+
+struct Checker<'a> {
+    code: &'a str,
+    /// Where the next search for a name starts, so names used several
+    /// times are located in order.
+    offsets: HashMap<String, usize>,
+    diagnostics: Vec<Diagnostic>
+}
+
+impl<'a> Checker<'a> {
+    fn check(&mut self) {
+        let code = self.code;
+
+        let ast = match catch_unwind(|| Parser::new(code).parse()) {
+            Ok(ast) => ast,
+            Err(err) => {
+                self.push((1, 1), format!("parse error: {}", panic_message(err)));
+                return;
+            }
+        };
+
+        for node in &ast {
+            // Data routes are sent as JSON, what they return is not part of
+            // the page script.
+            if matches!(node, ASTNode::Ret(_)) && returned_html(node).is_none() {
+                continue;
+            }
+
+            self.check_script(node);
+        }
+
+        let html = match ast.iter().find_map(returned_html) {
+            Some(html) => html,
+            None => return
+        };
+
+        self.offsets.clear();
+        self.check_html(html);
+
+        if self.diagnostics.is_empty() {
+            self.dry_run(html);
+        }
+    }
+
+    fn check_script(&mut self, node: &ASTNode) {
+        if is_static_params(node) {
+            return;
+        }
+
+        let pos = match statement_name(node) {
+            Some(name) => self.locate(name),
+            None => (1, 1)
+        };
+
+        let mut gen = JSGen::new();
+
+        if let Err(err) = catch_unwind(AssertUnwindSafe(|| gen.process_node(node))) {
+            self.push(pos, format!("unsupported in page script: {}", panic_message(err)));
+        }
+    }
+
+    fn check_html(&mut self, node: &ASTNode) {
+        let pos = self.locate("Html");
+
+        let props = match node {
+            ASTNode::ObjIns(o) => &o.props,
+            _ => return
+        };
+
+        for prop in props {
+            let prop_pos = self.locate(&prop.name);
+
+            match prop.name.as_str() {
+                "head" => self.check_head(&prop.value),
+                "body" => self.check_children(&prop.value),
+                "lang" => {},
+                name => self.push(prop_pos, format!("unknown Html property {}", name))
+            }
+        }
+
+        if props.iter().all(|p| p.name != "body") {
+            self.push(pos, "Html has no body".to_string());
+        }
+    }
+
+    fn check_head(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::ObjIns(o) => {
+                for prop in &o.props {
+                    let pos = self.locate(&prop.name);
+
+                    if !HEAD_PROPS.contains(&prop.name.as_str()) {
+                        self.push(pos, format!("unknown Head property {}", prop.name));
+                    }
+                }
+            },
+            ASTNode::Lit(_) | ASTNode::Array(_) => {
+                let pos = self.locate("head");
+                self.push(pos, "head must be a Head object".to_string());
+            },
+            _ => {}
+        }
+    }
+
+    fn check_children(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Array(a) => {
+                for item in &a.items {
+                    self.check_child(item);
+                }
+            },
+            node => self.check_child(node)
+        }
+    }
+
+    fn check_child(&mut self, node: &ASTNode) {
+        match node {
+            // `None` children are left out.
+            ASTNode::Lit(Value::Str(_) | Value::Int(_) | Value::Float(_) | Value::None) => {},
+            ASTNode::Lit(value) => {
+                let pos = match value {
+                    Value::Bool(b) => self.locate(&b.to_string()),
+                    _ => (1, 1)
+                };
+
+                self.push(pos, format!("{:?} can not be rendered", value));
+            },
+            ASTNode::ObjIns(_) => self.check_element(node),
+            // Computed at runtime.
+            _ => {}
+        }
+    }
+
+    fn check_element(&mut self, node: &ASTNode) {
+        let o = match node {
+            ASTNode::ObjIns(o) => o,
+            _ => return
+        };
+
+        match &o.name {
+            Some(name) => {
+                let pos = self.locate(name);
+
+                let known = HtmlElType::from_name(name).is_some() ||
+                    name == RAW_HTML ||
+                    SPECIAL_ELEMENTS.contains(&name.as_str());
+
+                if !known {
+                    self.push(pos, format!("unknown element {}", name));
+                }
+            },
+            None => {
+                let pos = match o.props.first() {
+                    Some(prop) => self.locate(&prop.name),
+                    None => (1, 1)
+                };
+
+                self.push(pos, "element without a name".to_string());
+            }
+        }
+
+        for prop in &o.props {
+            let pos = self.locate(&prop.name);
+
+            match (prop.name.as_str(), &*prop.value) {
+                ("children", value) => self.check_children(value),
+                ("style", style @ ASTNode::ObjIns(_)) => self.check_style(style),
+                ("style", ASTNode::Lit(_)) => {
+                    self.push(pos, "style must be an object".to_string());
+                },
+                ("text", ASTNode::Lit(Value::Str(_) | Value::Int(_) | Value::Float(_))) => {},
+                ("text", ASTNode::Lit(value)) => {
+                    self.push(pos, format!("text can not be {:?}", value));
+                },
+                _ => {}
+            }
+        }
+    }
+
+    /// Checks the properties of a style object, and of the pseudo-class and
+    /// breakpoint objects in it.
+    fn check_style(&mut self, node: &ASTNode) {
+        let style = match node {
+            ASTNode::ObjIns(o) => o,
+            _ => return
+        };
+
+        for prop in &style.props {
+            let pos = self.locate(&prop.name);
+
+            if CSSProps::is_variant(&prop.name) {
+                match &*prop.value {
+                    variant @ ASTNode::ObjIns(_) => self.check_style(variant),
+                    ASTNode::Lit(_) => self.push(pos, format!("style {} must be an object", prop.name)),
+                    _ => {}
+                }
+
+                continue;
+            }
+
+            // Properties without a kind, like `textShadow`, are passed on
+            // to the browser as is.
+            if !CSSProps::is_known(&prop.name) {
+                continue;
+            }
+
+            if let ASTNode::Lit(value) = &*prop.value {
+                if let Err(err) = CSSProps::validate(&prop.name, value) {
+                    self.push(pos, err);
+                }
+            }
+        }
+    }
+
+    /// Converts the literal parts of the page like the server would, to
+    /// catch what the checks above do not know about.
+    fn dry_run(&mut self, html: &ASTNode) {
+        let value = match static_value(html) {
+            Some(value) => value,
+            None => return
+        };
+
+        if let Err(err) = Html::try_from(value) {
+            let pos = match err.clone().locate(self.code).position {
+                Some(pos) => pos,
+                None => self.locate("Html")
+            };
+
+            self.push(pos, err.to_string());
+        }
+    }
+
+    /// Position of the next whole word occurrence of `name`.
+    fn locate(&mut self, name: &str) -> (usize, usize) {
+        let start = self.offsets.get(name).copied().unwrap_or_default();
+
+        let found = self.code[start..]
+            .match_indices(name)
+            .map(|(i, _)| start + i)
+            .find(|&i| {
+                let before = self.code[..i].chars().next_back();
+                let after = self.code[i + name.len()..].chars().next();
+
+                !before.map(is_ident_char).unwrap_or(false) &&
+                    !after.map(is_ident_char).unwrap_or(false)
+            });
+
+        let i = match found {
+            Some(i) => i,
+            None => return (1, 1)
+        };
+
+        self.offsets.insert(name.to_string(), i + name.len());
+
+        let line_start = self.code[..i].rfind('\n').map(|n| n + 1).unwrap_or_default();
+        let line = self.code[..i].matches('\n').count() + 1;
+        let column = self.code[line_start..i].chars().count() + 1;
+
+        (line, column)
+    }
+
+    fn push(&mut self, (line, column): (usize, usize), message: String) {
+        self.diagnostics.push(Diagnostic {
+            line: line,
+            column: column,
+            message: message
+        });
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The `Html` literal `node` returns, if it is such a return statement.
+fn returned_html(node: &ASTNode) -> Option<&ASTNode> {
+    match node {
+        ASTNode::Ret(r) => match &*r.value {
+            Some(value @ ASTNode::ObjIns(o)) if o.name.as_deref() == Some("Html") => Some(value),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+/// Name to locate a top level statement by.
+fn statement_name(node: &ASTNode) -> Option<&str> {
+    match node {
+        ASTNode::Assign(a) => match &*a.left {
+            ASTNode::Ident(name) => Some(name),
+            _ => None
+        },
+        ASTNode::Ret(_) => Some("return"),
+        ASTNode::Ident(name) => Some(name),
+        _ => None
+    }
+}
+
+/// Value of the literal parts of `node`. Anything computed at runtime is
+/// left out.
+fn static_value(node: &ASTNode) -> Option<Value> {
+    match node {
+        ASTNode::Lit(value) => Some(value.clone()),
+        ASTNode::Array(a) => Some(Value::List(a.items.iter().filter_map(static_value).collect())),
+        ASTNode::ObjIns(o) if o.name.as_deref().map(|n| SPECIAL_ELEMENTS.contains(&n)).unwrap_or(false) => None,
+        ASTNode::ObjIns(o) => {
+            let props = o.props.iter()
+                .filter_map(|p| static_value(&p.value).map(|v| (p.name.as_str(), v)))
+                .collect();
+
+            Some(value::obj(o.name.as_deref(), props))
+        },
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_page_has_no_diagnostics() {
+        let diagnostics = check(r#"
+        return Html {
+            head: Head {
+                title: "hello"
+            },
+            body: [
+                Div {
+                    style: {
+                        display: "flex"
+                    },
+                    children: [H1 { text: "hello" }]
+                }
+            ]
+        }"#);
+
+        assert_eq!(diagnostics, vec![]);
+    }
+
+    #[test]
+    fn reports_unknown_elements_and_invalid_styles() {
+        let diagnostics = check(r#"return Html {
+    body: [
+        Marquee {
+            text: "hello"
+        },
+        Div {
+            style: {
+                display: "flexbox",
+                textShadow: "0 1px 2px black"
+            }
+        }
+    ]
+}"#);
+
+        let diagnostics = diagnostics.iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(diagnostics, vec![
+            "3:9: unknown element Marquee".to_string(),
+            "8:17: invalid display \"flexbox\"".to_string()
+        ]);
+    }
+
+    #[test]
+    fn reports_literal_children_at_their_position() {
+        let diagnostics = check(r#"return Html {
+    body: [
+        1.5
+        true
+    ]
+}"#);
+
+        assert_eq!(diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>(), vec![
+            "4:9: Bool(true) can not be rendered".to_string()
+        ]);
+    }
+
+    #[test]
+    fn data_routes_are_only_parsed() {
+        assert_eq!(check("return [1, 2]"), vec![]);
+    }
+
+    #[test]
+    fn checks_script_without_html_literal() {
+        let diagnostics = check("total = 1 + 2\nreturn [total]");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+        assert!(diagnostics[0].message.starts_with("unsupported in page script"));
+    }
+}
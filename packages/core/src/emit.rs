@@ -0,0 +1,161 @@
+use std::panic::catch_unwind;
+
+use flexscript::ASTNode;
+use flexscript::Parser;
+use serde_json::json;
+
+use crate::errors::panic_message;
+use crate::js::JSGen;
+use crate::js::JSNode;
+use crate::monolith::Monolith;
+use crate::value;
+
+/// Step of the page pipeline to show, see `emit`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmitStage {
+    /// FlexScript syntax tree
+    Ast,
+    /// Tree `JSGen` builds from the syntax tree
+    JsNode,
+    /// Page script sent to the browser
+    Js,
+    /// Response of rendering the page
+    Html
+}
+
+/// Output of one stage as json and as human readable text.
+#[derive(Debug, Clone)]
+pub struct Emitted {
+    pub json: serde_json::Value,
+    pub pretty: String
+}
+
+/// Runs `code` through the page pipeline up to `stage`, without a server.
+pub async fn emit(code: &str, stage: EmitStage) -> anyhow::Result<Emitted> {
+    match stage {
+        EmitStage::Ast => {
+            let ast = parse(code)?;
+
+            Ok(Emitted {
+                json: serde_json::Value::Array(ast.iter().map(ast_json).collect()),
+                pretty: format!("{:#?}", ast)
+            })
+        },
+        EmitStage::JsNode => {
+            let js_node = gen(code)?;
+
+            Ok(Emitted {
+                json: serde_json::to_value(&js_node)?,
+                pretty: format!("{:#?}", js_node)
+            })
+        },
+        EmitStage::Js => {
+            let js = gen(code)?.to_string();
+
+            Ok(Emitted {
+                json: serde_json::Value::String(js.clone()),
+                pretty: js
+            })
+        },
+        EmitStage::Html => {
+            let res = Monolith::new()
+                .workers(1)
+                .add("/", code)
+                .render("/")
+                .await?;
+
+            let body = hyper::body::to_bytes(res.into_body()).await?;
+            let html = String::from_utf8_lossy(&body).to_string();
+
+            Ok(Emitted {
+                json: serde_json::Value::String(html.clone()),
+                pretty: html
+            })
+        }
+    }
+}
+
+fn parse(code: &str) -> anyhow::Result<Vec<ASTNode>> {
+    catch_unwind(|| Parser::new(code).parse())
+        .map_err(|err| anyhow::anyhow!("parse error: {}", panic_message(err)))
+}
+
+fn gen(code: &str) -> anyhow::Result<JSNode> {
+    let ast = parse(code)?;
+
+    catch_unwind(|| JSGen::new().gen(ast))
+        .map_err(|err| anyhow::anyhow!("js generation failed: {}", panic_message(err)))
+}
+
+/// Json form of a syntax tree node. Nodes without a dedicated form are
+/// given as their debug output.
+fn ast_json(node: &ASTNode) -> serde_json::Value {
+    match node {
+        ASTNode::Ident(name) => json!({ "type": "Ident", "name": name }),
+        ASTNode::Lit(value) => json!({ "type": "Lit", "value": value::to_json(value) }),
+        ASTNode::Assign(a) => json!({
+            "type": "Assign",
+            "left": ast_json(&a.left),
+            "right": ast_json(&a.right)
+        }),
+        ASTNode::ObjIns(o) => json!({
+            "type": "ObjIns",
+            "name": o.name,
+            "props": o.props.iter()
+                .map(|p| json!({ "name": p.name, "value": ast_json(&p.value) }))
+                .collect::<Vec<_>>()
+        }),
+        ASTNode::Array(a) => json!({
+            "type": "Array",
+            "items": a.items.iter().map(ast_json).collect::<Vec<_>>()
+        }),
+        ASTNode::Call(c) => json!({
+            "type": "Call",
+            "callee": ast_json(&c.callee),
+            "args": c.args.iter().map(ast_json).collect::<Vec<_>>()
+        }),
+        ASTNode::Fun(f) => json!({
+            "type": "Fun",
+            "params": f.params.iter().map(|p| p.name.clone()).collect::<Vec<_>>(),
+            "body": f.body.iter().map(ast_json).collect::<Vec<_>>()
+        }),
+        ASTNode::ProbAccess(p) => json!({
+            "type": "ProbAccess",
+            "object": ast_json(&p.object),
+            "property": p.property
+        }),
+        ASTNode::Ret(r) => json!({
+            "type": "Ret",
+            "value": (*r.value).as_ref().map(ast_json)
+        }),
+        node => {
+            let debug = format!("{:?}", node);
+            let typ = debug.split(|c: char| !c.is_alphanumeric()).next().unwrap_or_default().to_string();
+
+            json!({ "type": typ, "debug": debug })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn emits_ast_as_json() {
+        let emitted = emit("a = 5", EmitStage::Ast).await.unwrap();
+
+        assert_eq!(emitted.json, json!([{
+            "type": "Assign",
+            "left": { "type": "Ident", "name": "a" },
+            "right": { "type": "Lit", "value": 5 }
+        }]));
+    }
+
+    #[tokio::test]
+    async fn emits_js() {
+        let emitted = emit("a = 5", EmitStage::Js).await.unwrap();
+
+        assert_eq!(emitted.pretty, "const a = 5\n");
+    }
+}
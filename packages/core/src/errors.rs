@@ -0,0 +1,141 @@
+use std::any::Any;
+use std::fmt;
+
+use hyper::Body;
+use hyper::Method;
+use hyper::Response;
+use hyper::StatusCode;
+use hyper::header;
+
+use crate::html::escape_text;
+use crate::reload::inject_client;
+
+#[derive(Debug)]
+pub enum PageError {
+    BadRequest(String),
+    NotFound,
+    MethodNotAllowed(Vec<Method>),
+    PayloadTooLarge,
+    Internal {
+        route: String,
+        message: String
+    }
+}
+
+impl PageError {
+    pub fn internal(route: &str, message: impl ToString) -> Self {
+        PageError::Internal {
+            route: route.to_string(),
+            message: message.to_string()
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            PageError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            PageError::NotFound => StatusCode::NOT_FOUND,
+            PageError::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            PageError::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            PageError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+
+    /// Builds the error response. Route and error message are only shown
+    /// in development mode, where the page also live reloads.
+    pub fn into_response(self, dev: bool) -> Response<Body> {
+        let status = self.status();
+        let title = format!(
+            "{} {}",
+            status.as_u16(),
+            status.canonical_reason().unwrap_or_default()
+        );
+
+        let detail = match &self {
+            PageError::Internal { route, message } if dev => {
+                log::error!("route {} failed: {}", route, message);
+
+                format!(
+                    "<p>Route <code>{}</code> failed</p><pre>{}</pre>",
+                    escape_text(route),
+                    escape_text(message)
+                )
+            },
+            PageError::Internal { route, message } => {
+                log::error!("route {} failed: {}", route, message);
+
+                String::new()
+            },
+            PageError::BadRequest(message) => format!("<p>{}</p>", escape_text(message)),
+            _ => String::new()
+        };
+
+        let mut body = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body><h1>{}</h1>{}</body>\n</html>",
+            title,
+            title,
+            detail
+        );
+
+        if dev {
+            body = inject_client(body);
+        }
+
+        let mut builder = Response::builder()
+            .status(status)
+            .header(header::CONTENT_TYPE, "text/html; charset=utf-8");
+
+        if let PageError::MethodNotAllowed(methods) = &self {
+            let allow = methods.iter()
+                .map(|m| m.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ");
+
+            builder = builder.header(header::ALLOW, allow);
+        }
+
+        builder.body(Body::from(body)).unwrap()
+    }
+}
+
+impl fmt::Display for PageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageError::Internal { route, message } => write!(f, "route {} failed: {}", route, message),
+            PageError::BadRequest(message) => write!(f, "bad request: {}", message),
+            err => write!(f, "{}", err.status())
+        }
+    }
+}
+
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        return s.to_string();
+    }
+
+    if let Some(s) = payload.downcast_ref::<String>() {
+        return s.clone();
+    }
+
+    "unknown panic".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_not_allowed_lists_allowed_methods() {
+        let res = PageError::MethodNotAllowed(vec![Method::GET, Method::HEAD])
+            .into_response(false);
+
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "GET, HEAD");
+    }
+
+    #[test]
+    fn panic_payloads_become_messages() {
+        let err = std::panic::catch_unwind(|| panic!("not implemented: {}", 1)).unwrap_err();
+
+        assert_eq!(panic_message(err), "not implemented: 1");
+    }
+}
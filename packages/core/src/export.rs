@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::panic::catch_unwind;
+use std::path::Path;
+use std::path::PathBuf;
+
+use flexscript::ASTNode;
+use flexscript::Parser;
+use flexscript::Value;
+use hyper::Body;
+use hyper::Request;
+use hyper::Response;
+use hyper::header;
+use percent_encoding::AsciiSet;
+use percent_encoding::CONTROLS;
+use percent_encoding::percent_decode_str;
+use percent_encoding::utf8_percent_encode;
+
+use crate::errors::panic_message;
+use crate::monolith::App;
+
+/// Name of the variable parameterized pages list their params in.
+pub const STATIC_PARAMS: &str = "static_params";
+
+/// Characters escaped in param values, on top of controls and non-ascii.
+const PARAM: &AsciiSet = &CONTROLS
+    .add(b' ').add(b'"').add(b'#').add(b'%').add(b'<').add(b'>').add(b'?').add(b'`').add(b'{').add(b'}');
+
+/// Renders every GET route of `app` into `out_dir`. Html pages are written
+/// as `<path>/index.html` so plain static hosting serves them at the same
+/// url. Returns the written files.
+pub(crate) async fn export(app: App, out_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut written = vec![];
+
+    for (pattern, code) in app.pages() {
+        let params_list = match pattern.contains(':') {
+            false => vec![HashMap::new()],
+            true => match static_params(&code) {
+                Ok(Some(list)) => list,
+                Ok(None) => {
+                    log::warn!("skipping {}, the page has no {} list", pattern, STATIC_PARAMS);
+                    continue;
+                },
+                Err(err) => {
+                    log::error!("skipping {}: {}", pattern, err);
+                    continue;
+                }
+            }
+        };
+
+        for params in params_list {
+            let path = match fill_params(&pattern, &params) {
+                Ok(path) => path,
+                Err(err) => {
+                    log::error!("skipping {} {:?}: {}", pattern, params, err);
+                    continue;
+                }
+            };
+
+            let res = render(&app, &path).await?;
+
+            if !res.status().is_success() {
+                log::warn!("skipping {}, it responded with {}", path, res.status());
+                continue;
+            }
+
+            let file = out_dir.join(file_name(&path, &res));
+            write(&file, res).await?;
+            written.push(file);
+        }
+    }
+
+    if app.has_not_found_page() {
+        let req = Request::get("/404").body(Body::empty())?;
+        let res = app.render_not_found(req).await
+            .map_err(|err| anyhow::anyhow!("404 page: {}", err))?;
+        let file = out_dir.join("404.html");
+
+        write(&file, res).await?;
+        written.push(file);
+    }
+
+    for mount in app.statics() {
+        mount.export(out_dir)?;
+    }
+
+    Ok(written)
+}
+
+async fn render(app: &App, path: &str) -> anyhow::Result<Response<Body>> {
+    let req = Request::get(path).body(Body::empty())?;
+
+    app.render(req).await
+        .map_err(|err| anyhow::anyhow!("{}: {}", path, err))
+}
+
+async fn write(file: &Path, res: Response<Body>) -> anyhow::Result<()> {
+    let body = hyper::body::to_bytes(res.into_body()).await?;
+
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(file, body)?;
+
+    Ok(())
+}
+
+fn file_name(path: &str, res: &Response<Body>) -> PathBuf {
+    let content_type = res.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    // Hosts look files up by the decoded path.
+    let rel = PathBuf::from(percent_decode_str(path.trim_matches('/')).decode_utf8_lossy().to_string());
+
+    if content_type.starts_with("text/html") {
+        return rel.join("index.html");
+    }
+
+    match rel.as_os_str().is_empty() {
+        true if content_type.starts_with("application/json") => PathBuf::from("index.json"),
+        true => PathBuf::from("index.txt"),
+        false => rel
+    }
+}
+
+/// Reads the params a page lists at the top level, for example
+/// `static_params = [{ id: "1" }, { id: "2" }]`. Only literal values are
+/// supported since the list is read without running the page.
+pub fn static_params(code: &str) -> Result<Option<Vec<HashMap<String, String>>>, String> {
+    let ast = catch_unwind(|| Parser::new(code).parse())
+        .map_err(panic_message)?;
+
+    let list = ast.iter().find_map(|node| match node {
+        ASTNode::Assign(a) => match &*a.left {
+            ASTNode::Ident(name) if name == STATIC_PARAMS => Some(&a.right),
+            _ => None
+        },
+        _ => None
+    });
+
+    let items = match list.map(|l| &**l) {
+        Some(ASTNode::Array(a)) => &a.items,
+        Some(_) => return Err(format!("{} must be a list of objects", STATIC_PARAMS)),
+        None => return Ok(None)
+    };
+
+    let mut params_list = vec![];
+
+    for item in items {
+        let obj = match item {
+            ASTNode::ObjIns(o) => o,
+            _ => return Err(format!("{} must be a list of objects", STATIC_PARAMS))
+        };
+
+        let mut params = HashMap::new();
+
+        for prop in &obj.props {
+            let value = match &*prop.value {
+                ASTNode::Lit(Value::Str(s)) => s.clone(),
+                ASTNode::Lit(Value::Int(i)) => i.to_string(),
+                ASTNode::Lit(Value::Float(f)) => f.to_string(),
+                _ => return Err(format!("{}.{} must be a literal", STATIC_PARAMS, prop.name))
+            };
+
+            params.insert(prop.name.clone(), value);
+        }
+
+        params_list.push(params);
+    }
+
+    Ok(Some(params_list))
+}
+
+/// Replaces the `:name` segments of `pattern` with `params`, percent
+/// encoded.
+pub fn fill_params(pattern: &str, params: &HashMap<String, String>) -> Result<String, String> {
+    let segments = pattern.split('/')
+        .map(|segment| {
+            let name = match segment.strip_prefix(':') {
+                Some(name) => name,
+                None => return Ok(segment.to_string())
+            };
+
+            match params.get(name) {
+                Some(value) if value.is_empty() || value.contains('/') || value == ".." => {
+                    Err(format!("param {} can not be {:?}", name, value))
+                },
+                Some(value) => Ok(utf8_percent_encode(value, PARAM).to_string()),
+                None => Err(format!("missing param {}", name))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Monolith;
+
+    use super::*;
+
+    #[test]
+    fn reads_static_params() {
+        let params = static_params(r#"
+            static_params = [{ id: "1" }, { id: 2 }]
+            return [1]
+        "#).unwrap().unwrap();
+
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0]["id"], "1");
+        assert_eq!(params[1]["id"], "2");
+
+        assert_eq!(static_params("return [1]"), Ok(None));
+    }
+
+    #[test]
+    fn fills_params() {
+        let params = vec![("id".to_string(), "42".to_string())].into_iter().collect();
+
+        assert_eq!(fill_params("/todo/:id/edit", &params), Ok("/todo/42/edit".to_string()));
+        assert!(fill_params("/user/:name", &params).is_err());
+
+        let params = vec![("name".to_string(), "a b?".to_string())].into_iter().collect();
+        assert_eq!(fill_params("/user/:name", &params), Ok("/user/a%20b%3F".to_string()));
+    }
+
+    #[tokio::test]
+    async fn exports_routes() {
+        let out_dir = std::env::temp_dir().join(format!("monolith-export-{}", std::process::id()));
+
+        let written = Monolith::new()
+            .add("/", "return [1, 2]")
+            .export(&out_dir)
+            .await
+            .unwrap();
+
+        assert_eq!(written, vec![out_dir.join("index.json")]);
+        assert_eq!(std::fs::read_to_string(&written[0]).unwrap(), "[1,2]");
+
+        std::fs::remove_dir_all(&out_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn exports_not_found_page_and_skips_bad_params() {
+        let out_dir = std::env::temp_dir().join(format!("monolith-export-404-{}", std::process::id()));
+
+        let written = Monolith::new()
+            .add("/:a/:b", r#"
+                static_params = [{ a: "x y", b: "1" }, { a: "x" }]
+                return [params.a]
+            "#)
+            .not_found(r#"return Html { body: [H1 { text: "missing" }] }"#)
+            .export(&out_dir)
+            .await
+            .unwrap();
+
+        assert_eq!(written, vec![out_dir.join("x y/1"), out_dir.join("404.html")]);
+        assert_eq!(std::fs::read_to_string(&written[0]).unwrap(), r#"["x y"]"#);
+        assert!(std::fs::read_to_string(&written[1]).unwrap().contains("<h1>missing</h1>"));
+
+        std::fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+/// Values of form fields by name, in the order they were sent. Checkboxes
+/// and multiple selects send the same name several times.
+pub type Fields = HashMap<String, Vec<String>>;
+
+/// Parsed `application/x-www-form-urlencoded` or `multipart/form-data`
+/// request body.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormData {
+    pub fields: Fields,
+    pub files: Vec<FormFile>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormFile {
+    pub field: String,
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub content: Vec<u8>
+}
+
+/// Parses a form body based on its content type. Returns `Ok(None)` when
+/// the body is not a form.
+pub fn parse_form(content_type: Option<&str>, body: &[u8]) -> Result<Option<FormData>, String> {
+    let content_type = match content_type {
+        Some(c) => c,
+        None => return Ok(None)
+    };
+
+    let (mime, params) = match content_type.split_once(';') {
+        Some((mime, params)) => (mime.trim(), params),
+        None => (content_type.trim(), "")
+    };
+
+    match mime.to_ascii_lowercase().as_str() {
+        "application/x-www-form-urlencoded" => {
+            let mut form = FormData::default();
+
+            for (name, value) in form_urlencoded::parse(body).into_owned() {
+                form.fields.entry(name).or_default().push(value);
+            }
+
+            Ok(Some(form))
+        },
+        "multipart/form-data" => {
+            let boundary = params.split(';')
+                .filter_map(|p| p.trim().split_once('='))
+                .find(|(k, _)| k.eq_ignore_ascii_case("boundary"))
+                .map(|(_, v)| v.trim_matches('"').to_string())
+                .ok_or("multipart body is missing boundary")?;
+
+            parse_multipart(&boundary, body).map(Some)
+        },
+        _ => Ok(None)
+    }
+}
+
+fn parse_multipart(boundary: &str, body: &[u8]) -> Result<FormData, String> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    // Delimiters after the first start on a line of their own, so content
+    // with `--boundary` in the middle of a line is not cut there.
+    let next_delimiter = format!("\r\n--{}", boundary).into_bytes();
+    let mut form = FormData::default();
+
+    let mut rest = match body.strip_prefix(delimiter.as_slice()) {
+        Some(rest) => rest,
+        None => match find(body, &next_delimiter) {
+            Some(i) => &body[i + next_delimiter.len()..],
+            None => return Err("multipart body has no parts".to_string())
+        }
+    };
+
+    loop {
+        if rest.starts_with(b"--") {
+            break;
+        }
+
+        rest = rest.strip_prefix(b"\r\n").ok_or("malformed multipart delimiter")?;
+
+        let end = find(rest, &next_delimiter).ok_or("unterminated multipart part")?;
+        let part = &rest[..end];
+        rest = &rest[end + next_delimiter.len()..];
+
+        let header_end = find(part, b"\r\n\r\n").ok_or("multipart part is missing headers")?;
+        let headers = String::from_utf8_lossy(&part[..header_end]);
+        let content = &part[header_end + 4..];
+
+        let mut name = None;
+        let mut filename = None;
+        let mut content_type = None;
+
+        for line in headers.split("\r\n") {
+            let (key, value) = match line.split_once(':') {
+                Some(h) => h,
+                None => continue
+            };
+
+            if key.trim().eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            }
+
+            if key.trim().eq_ignore_ascii_case("content-disposition") {
+                for param in value.split(';').skip(1) {
+                    match param.trim().split_once('=') {
+                        Some(("name", v)) => name = Some(v.trim_matches('"').to_string()),
+                        Some(("filename", v)) => filename = Some(v.trim_matches('"').to_string()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let name = name.ok_or("multipart part is missing name")?;
+
+        match filename {
+            Some(filename) => {
+                form.files.push(FormFile {
+                    field: name,
+                    filename: filename,
+                    content_type: content_type,
+                    content: content.to_vec()
+                });
+            },
+            None => {
+                form.fields.entry(name).or_default().push(String::from_utf8_lossy(content).to_string());
+            }
+        }
+    }
+
+    Ok(form)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urlencoded_form() {
+        let form = parse_form(
+            Some("application/x-www-form-urlencoded"),
+            b"name=Buy+milk&done=on&tag=a&tag=b"
+        ).unwrap().unwrap();
+
+        assert_eq!(form.fields["name"], vec!["Buy milk"]);
+        assert_eq!(form.fields["done"], vec!["on"]);
+        assert_eq!(form.fields["tag"], vec!["a", "b"]);
+    }
+
+    #[test]
+    fn multipart_form() {
+        let body = b"--XyZ\r\n\
+Content-Disposition: form-data; name=\"name\"\r\n\
+\r\n\
+Buy milk --XyZ\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"name\"\r\n\
+\r\n\
+Buy eggs\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"photo\"; filename=\"milk.txt\"\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+white\r\n\
+--XyZ--\r\n";
+
+        let form = parse_form(Some("multipart/form-data; boundary=XyZ"), body)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(form.fields["name"], vec!["Buy milk --XyZ", "Buy eggs"]);
+        assert_eq!(form.files, vec![FormFile {
+            field: "photo".to_string(),
+            filename: "milk.txt".to_string(),
+            content_type: Some("text/plain".to_string()),
+            content: b"white".to_vec()
+        }]);
+    }
+
+    #[test]
+    fn other_content_types_are_not_forms() {
+        assert_eq!(parse_form(Some("application/json"), b"{}"), Ok(None));
+        assert_eq!(parse_form(None, b""), Ok(None));
+    }
+}
@@ -3,11 +3,13 @@ use std::{sync::Arc, pin::Pin, task::{Context, Poll}, collections::HashMap};
 use futures_util::Stream;
 use hyper::upgrade::Upgraded;
 use hyper_tungstenite::{HyperWebsocket, tungstenite::Message, WebSocketStream};
+use hyper_tungstenite::tungstenite::protocol::CloseFrame;
+use hyper_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use tokio::sync::{mpsc, Mutex};
 use futures_util::StreamExt;
 use futures_util::SinkExt;
 
-use crate::{gui::{diff::{self, diff}, types::{ClientAction, Replace}}, SetQuery};
+use crate::{gui::{diff::{self, diff}, types::{ClientAction, Replace}}, SetQuery, ShutdownSignal};
 
 use super::{types::ClientEvent, gui::Item};
 
@@ -151,7 +153,8 @@ struct Worker {
     ws: WebSocketStream<Upgraded>,
     cmd_recv: mpsc::UnboundedReceiver<Command>,
     event_sender: mpsc::UnboundedSender<ClientEvent>,
-    last_root: Option<Item>
+    last_root: Option<Item>,
+    shutdown: ShutdownSignal
 }
 
 impl Worker {
@@ -294,24 +297,46 @@ impl Worker {
                         }
                     }
                 }
+                _ = self.shutdown.wait() => {
+                    log::info!("Server shutting down, closing websocket");
+
+                    let frame = CloseFrame {
+                        code: CloseCode::Away,
+                        reason: "server shutting down".into()
+                    };
+
+                    if let Err(err) = self.ws.send(Message::Close(Some(frame))).await {
+                        log::error!("Error sending close frame: {}", err);
+                    }
+
+                    break;
+                }
             };
         }
     }
 }
 
 
-pub fn create_ui_client(id: usize, websocket: HyperWebsocket) -> Client {
+pub fn create_ui_client(id: usize, websocket: HyperWebsocket, shutdown: ShutdownSignal) -> Client {
     let (event_sender, event_receiver) = mpsc::unbounded_channel();
     let (cmd_sender, cmd_receiver) = mpsc::unbounded_channel();
 
     tokio::spawn(async move {
-        let ws = websocket.await.unwrap();
+        let ws = match websocket.await {
+            Ok(ws) => ws,
+            Err(err) => {
+                log::error!("Error upgrading websocket: {}", err);
+
+                return;
+            }
+        };
 
         Worker { 
             ws: ws,
             cmd_recv: cmd_receiver,
             event_sender: event_sender,
-            last_root: None
+            last_root: None,
+            shutdown: shutdown
         }.run().await;
     });
 
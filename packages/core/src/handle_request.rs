@@ -1,69 +1,38 @@
-use std::sync::{Arc, atomic::AtomicUsize};
-
-use anyhow::bail;
-use hyper::{Response, Body, Request};
+use hyper::{Response, Body, Request, StatusCode};
 use tokio::sync::mpsc;
 
-use crate::{create_ui_client, Client};
-
-pub fn serve_index() -> Response<Body>  {
-    let str = format!(r#"
-<html>
-    <head>
-        <title>Your app</title>
-    </head>
-    <body>
-        <script src="/index.js"></script>
-    </body>
-</html>"#, );
-
-    Response::new(Body::from(str))
-}
-
-const index_js_bytes: &[u8] = include_bytes!("../../../public/index.js");
-
-#[derive(Clone)]
-pub struct RequestContext {
-    pub next_client_id: Arc<AtomicUsize>,
-    pub client_sender: mpsc::Sender<Client>
-}
+use crate::{create_ui_client, Client, ShutdownSignal};
 
-pub async fn handle_request(mut req: Request<Body>, ctx: RequestContext) -> Result<Response<Body>, anyhow::Error> {
-    // Use the connection pool here
+/// Path `Client`s connect their websocket to.
+pub(crate) const UI_PATH: &str = "/ui";
 
-    log::info!("handle_request {}", req.uri());
+/// Upgrades `req` to a websocket and hands the `Client` to `clients`. The
+/// client is sent a close frame once `shutdown` fires.
+pub(crate) fn upgrade_ui(
+    mut req: Request<Body>,
+    id: usize,
+    clients: &mpsc::UnboundedSender<Client>,
+    shutdown: ShutdownSignal
+) -> Response<Body> {
+    log::info!("upgrade request {}", req.uri());
 
-    if hyper_tungstenite::is_upgrade_request(&req) {
-        log::info!("there is upgrade request");
+    let (response, websocket) = match hyper_tungstenite::upgrade(&mut req, None) {
+        Ok(upgrade) => upgrade,
+        Err(err) => {
+            log::error!("websocket upgrade failed: {}", err);
 
-        let (response, websocket) = hyper_tungstenite::upgrade(&mut req, None)?;
+            let mut res = Response::new(Body::from("invalid websocket upgrade"));
+            *res.status_mut() = StatusCode::BAD_REQUEST;
 
-        log::info!("websocket upgraded");
-
-        let id = ctx.next_client_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-
-        match req.uri().path() {
-            "/ui" => {
-                let client = create_ui_client(id, websocket);
+            return res;
+        }
+    };
 
-                ctx.client_sender.send(client).await.unwrap();
-            },
-            &_ | _ => {
-                bail!("not allowed url")
-            }
-        };
+    let client = create_ui_client(id, websocket, shutdown);
 
-        return Ok(response);
+    if clients.send(client).is_err() {
+        log::warn!("no receiver for ui clients, dropping client {}", id);
     }
 
-    match req.uri().path() {
-        "/index.js" => {
-            let response = Response::new(Body::from(index_js_bytes));
-            Ok(response)
-        },
-        _ => {
-            let response = serve_index();
-            Ok(response)
-        }
-    }
-}
\ No newline at end of file
+    response
+}
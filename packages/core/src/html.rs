@@ -1,9 +1,176 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
 use flexscript::Value;
 
 use crate::js::JSNode;
 
+/// Element whose `html` is inserted without escaping, for trusted content
+/// only: `RawHtml { html: "<b>bold</b>" }`.
+pub const RAW_HTML: &str = "RawHtml";
+
+/// Escapes text placed between tags.
+pub fn escape_text(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Escapes a value placed inside a double or single quoted attribute.
+pub fn escape_attr(s: &str) -> String {
+    escape_text(s)
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Keeps inline script content from closing the script element early.
+/// `<\/` means the same as `</` inside JavaScript strings.
+pub fn escape_script(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(i) = rest.find('<') {
+        escaped.push_str(&rest[..i]);
+
+        let tail = &rest[i..];
+        let lower = tail.get(..8).unwrap_or(tail).to_ascii_lowercase();
+
+        if lower.starts_with("</script") {
+            escaped.push_str("<\\/");
+            rest = &tail[2..];
+        } else if lower.starts_with("<!--") {
+            escaped.push_str("<\\!--");
+            rest = &tail[4..];
+        } else {
+            escaped.push('<');
+            rest = &tail[1..];
+        }
+    }
+
+    escaped.push_str(rest);
+
+    escaped
+}
+
+/// Step on the way to a value inside an `Html` value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    Prop(String),
+    Index(usize)
+}
+
+/// Why a value can not be converted to html, and where in it the problem is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlError {
+    /// Outermost first, like `body[2].children[0].style.display`.
+    pub path: Vec<PathSegment>,
+    pub message: String,
+    /// Line and column in the page code, see `locate`.
+    pub position: Option<(usize, usize)>
+}
+
+impl HtmlError {
+    pub fn new(message: impl ToString) -> Self {
+        HtmlError {
+            path: vec![],
+            message: message.to_string(),
+            position: None
+        }
+    }
+
+    /// Puts property `name` in front of the path, for the error of a value
+    /// inside it.
+    pub fn in_prop(mut self, name: &str) -> Self {
+        self.path.insert(0, PathSegment::Prop(name.to_string()));
+        self
+    }
+
+    /// Puts list index `index` in front of the path.
+    pub fn in_index(mut self, index: usize) -> Self {
+        self.path.insert(0, PathSegment::Index(index));
+        self
+    }
+
+    pub fn path_string(&self) -> String {
+        let mut s = String::new();
+
+        for segment in &self.path {
+            match segment {
+                PathSegment::Prop(name) if s.is_empty() => s.push_str(name),
+                PathSegment::Prop(name) => s.push_str(&format!(".{}", name)),
+                PathSegment::Index(i) => s.push_str(&format!("[{}]", i))
+            }
+        }
+
+        s
+    }
+
+    /// Sets the position of the value in `code`, the page it came from.
+    /// Values carry no position, so the property names of the path are
+    /// searched for one after another. Indexes are skipped, lists are often
+    /// computed, which makes the position a good guess and not more.
+    pub fn locate(mut self, code: &str) -> Self {
+        let mut start = 0;
+        let mut found = None;
+
+        for segment in &self.path {
+            let name = match segment {
+                PathSegment::Prop(name) => name,
+                PathSegment::Index(_) => continue
+            };
+
+            match find_prop(code, name, start) {
+                Some(i) => {
+                    found = Some(i);
+                    start = i + name.len();
+                },
+                None => break
+            }
+        }
+
+        self.position = found.map(|i| {
+            let line_start = code[..i].rfind('\n').map(|n| n + 1).unwrap_or_default();
+            let line = code[..i].matches('\n').count() + 1;
+
+            (line, code[line_start..i].chars().count() + 1)
+        });
+
+        self
+    }
+}
+
+impl fmt::Display for HtmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((line, column)) = self.position {
+            write!(f, "{}:{}: ", line, column)?;
+        }
+
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{}: {}", self.path_string(), self.message)
+        }
+    }
+}
+
+impl std::error::Error for HtmlError {}
+
+/// Offset of the next `name:` in `code` from `start` on.
+fn find_prop(code: &str, name: &str, start: usize) -> Option<usize> {
+    code[start..]
+        .match_indices(name)
+        .map(|(i, _)| start + i)
+        .find(|&i| {
+            let before = code[..i].chars().next_back();
+            let after = code[i + name.len()..].trim_start();
+
+            !before.map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false) && after.starts_with(':')
+        })
+}
+
 #[derive(Debug, Clone)]
 pub struct Html {
+    /// Language of the page, like `en`.
+    pub lang: Option<String>,
     pub head: Head,
     pub body: HtmlEl
 }
@@ -11,93 +178,207 @@ pub struct Html {
 impl Default for Html {
     fn default() -> Self {
         Html {
-            head: Head {
-                title: "".to_string(),
-                scripts: vec![]
-            },
-            body: HtmlEl {
-                typ: HtmlElType::Body,
-                style: CSSProps::default(),
-                children: vec![]
-            }
+            lang: None,
+            head: Head::default(),
+            body: HtmlEl::new(HtmlElType::Body)
         }
     }
 }
 
 impl ToString for Html {
     fn to_string(&self) -> String {
-        format!("<html>\n{}\n{}\n</html>", self.head.to_string(), self.body.to_string())
+        let mut sheet = StyleSheet::default();
+        let body = self.body.render(&mut sheet);
+
+        let lang = match &self.lang {
+            Some(lang) => format!(r#" lang="{}""#, escape_attr(lang)),
+            None => String::new()
+        };
+
+        format!("<!DOCTYPE html>\n<html{}>\n{}\n{}\n</html>", lang, self.head.render(&sheet), body)
+    }
+}
+
+impl TryFrom<Value> for Html {
+    type Error = HtmlError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let obj = match value {
+            Value::Obj(obj) => obj,
+            value => return Err(HtmlError::new(format!("expected Html, got {:?}", value)))
+        };
+
+        let mut html = Html::default();
+
+        for prop in obj.props {
+            match prop.name.as_ref() {
+                "head" => {
+                    html.head = Head::try_from(prop.value)
+                        .map_err(|err| err.in_prop("head"))?;
+                },
+                "lang" => match prop.value {
+                    Value::Str(s) => html.lang = Some(s),
+                    v => {
+                        let err = HtmlError::new(format!("lang must be a string, got {:?}", v));
+                        return Err(err.in_prop("lang"));
+                    }
+                },
+                "body" => {
+                    html.body.add_children(prop.value)
+                        .map_err(|err| err.in_prop("body"))?;
+                },
+                name => return Err(HtmlError::new(format!("unknown Html property {}", name)))
+            }
+        }
+
+        Ok(html)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub content: Option<JSNode>,
+    /// Url of an external script.
+    pub src: Option<String>,
+    pub defer: bool,
+    /// Loaded as an ES module, `type="module"`.
+    pub module: bool
+}
+
+impl Script {
+    /// Script with the page code generated by `JSGen`.
+    pub fn inline(content: JSNode) -> Self {
+        Script {
+            content: Some(content),
+            src: None,
+            defer: false,
+            module: false
+        }
+    }
+}
+
+impl ToString for Script {
+    fn to_string(&self) -> String {
+        let mut attrs = String::new();
+
+        if let Some(src) = &self.src {
+            attrs.push_str(&format!(r#" src="{}""#, escape_attr(src)));
+        }
+
+        if self.defer {
+            attrs.push_str(" defer");
+        }
+
+        if self.module {
+            attrs.push_str(r#" type="module""#);
+        }
+
+        let content = match (&self.src, &self.content) {
+            (None, Some(content)) => escape_script(&content.to_string()),
+            _ => String::new()
+        };
+
+        format!("<script{}>{}</script>", attrs, content)
     }
 }
 
-impl From<Value> for Html {
-    fn from(value: Value) -> Self {
+impl TryFrom<&Value> for Script {
+    type Error = HtmlError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let mut script = Script {
+            content: None,
+            src: None,
+            defer: false,
+            module: false
+        };
+
         match value {
+            Value::Str(src) => script.src = Some(src.to_string()),
             Value::Obj(obj) => {
-                let mut html = Html::default();
-
                 for prop in &obj.props {
-                    match prop.name.as_ref() {
-                        "head" => {
-                            html.head = Head::from(prop.value.clone());
-                        },
-                        "body" => {
-                            let mut el = HtmlEl {
-                                typ: HtmlElType::Body,
-                                style: CSSProps::default(),
-                                children: vec![]
-                            };
-
-                            match &prop.value {
-                                Value::List(list) => {
-                                    for item in list {
-                                        match item {
-                                            Value::Str(s) => {
-                                                el.children.push(Child::Text(s.to_string()));
-                                            },
-                                            Value::Int(i) => {
-                                                el.children.push(Child::Text(i.to_string()));
-                                            },
-                                            _ => {
-                                                el.children.push(Child::HtmlEl(HtmlEl::from(item.clone())));
-                                            }
-                                        }
-                                        // if let Value::Str(s) = item {
-                                        //     el.children.push(Child::Text(s.to_string()));
-                                        // } else {
-                                        //     el.children.push(Child::HtmlEl(HtmlEl::from(item.clone())));
-                                        // }
-                                    }
-                                },
-                                _ => {
-                                    el.children.push(Child::HtmlEl(HtmlEl::from(prop.value.clone())));
-                                }
-                            }
-
-                            html.body = el;
-                        },
-                        _ => todo!()
+                    match (prop.name.as_str(), &prop.value) {
+                        ("src", Value::Str(src)) => script.src = Some(src.to_string()),
+                        ("defer", Value::Bool(defer)) => script.defer = *defer,
+                        ("module", Value::Bool(module)) => script.module = *module,
+                        ("type", Value::Str(typ)) => script.module = typ == "module",
+                        (name, v) => {
+                            let err = HtmlError::new(format!("invalid script property {:?}", v));
+                            return Err(err.in_prop(name));
+                        }
                     }
                 }
-
-                html
             },
-            // Value::List(list) => {
-
-            // },
-            _ => todo!("{:?}", value)
+            _ => return Err(HtmlError::new(format!("script must be a url or an object, got {:?}", value)))
         }
+
+        Ok(script)
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Script {
-    pub content: Option<JSNode>
+/// Attributes of a `<meta>` or `<link>` tag, in the order they are written.
+pub type TagAttrs = Vec<(String, String)>;
+
+fn tag_attrs_to_string(attrs: &TagAttrs) -> String {
+    attrs.iter()
+        .map(|(name, value)| format!(r#" {}="{}""#, name, escape_attr(value)))
+        .collect()
+}
+
+/// Attributes from an object like `{ rel: "icon", href: "/icon.png" }`.
+fn tag_attrs(value: &Value) -> Result<TagAttrs, HtmlError> {
+    let obj = match value {
+        Value::Obj(obj) => obj,
+        _ => return Err(HtmlError::new(format!("expected an object of attributes, got {:?}", value)))
+    };
+
+    obj.props.iter()
+        .map(|prop| {
+            if is_inline_handler(&prop.name) {
+                return Err(HtmlError::new(format!("attribute {} is not allowed", prop.name)));
+            }
+
+            let value = match &prop.value {
+                Value::Str(s) => s.to_string(),
+                Value::Int(i) => i.to_string(),
+                Value::Float(f) => f.to_string(),
+                v => return Err(HtmlError::new(format!("invalid attribute {:?}", v)).in_prop(&prop.name))
+            };
+
+            Ok((kebab_case(&prop.name), value))
+        })
+        .collect()
+}
+
+/// Items of a list property, each converted with `f`. Errors get the
+/// index of the item.
+fn list_items<T>(value: &Value, f: impl Fn(&Value) -> Result<T, HtmlError>) -> Result<Vec<T>, HtmlError> {
+    let list = match value {
+        Value::List(list) => list,
+        v => return Err(HtmlError::new(format!("expected a list, got {:?}", v)))
+    };
+
+    list.iter()
+        .enumerate()
+        .map(|(i, item)| f(item).map_err(|err| err.in_index(i)))
+        .collect()
 }
 
+fn attr(name: &str, value: &str) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+/// Properties `Head` understands, see `Head::try_from`.
+pub const HEAD_PROPS: &[&str] = &[
+    "title", "charset", "viewport", "description", "meta", "og", "links", "stylesheets", "icon", "scripts"
+];
+
 #[derive(Debug, Clone)]
 pub struct Head {
     pub title: String,
+    pub charset: String,
+    pub meta: Vec<TagAttrs>,
+    pub links: Vec<TagAttrs>,
     pub scripts: Vec<Script>
 }
 
@@ -105,6 +386,9 @@ impl Default for Head {
     fn default() -> Self {
         Head {
             title: "".to_string(),
+            charset: "utf-8".to_string(),
+            meta: vec![],
+            links: vec![],
             scripts: vec![]
         }
     }
@@ -112,43 +396,128 @@ impl Default for Head {
 
 impl ToString for Head {
     fn to_string(&self) -> String {
-        let mut scripts = vec![];
-        for script in &self.scripts {
-            if let Some(content) = &script.content {
-                scripts.push(content.to_string());
-            }
-        }
+        self.render(&StyleSheet::default())
+    }
+}
+
+impl Head {
+    /// Renders the head with the rules of the classes used in the body.
+    pub fn render(&self, sheet: &StyleSheet) -> String {
+        let meta = self.meta.iter()
+            .map(|attrs| format!("<meta{}>", tag_attrs_to_string(attrs)))
+            .collect::<String>();
 
-        let scripts = scripts.iter()
-            .map(|p| format!("<script>{}</script>", p))
+        let links = self.links.iter()
+            .map(|attrs| format!("<link{}>", tag_attrs_to_string(attrs)))
+            .collect::<String>();
+
+        let scripts = self.scripts.iter()
+            .filter(|script| script.src.is_some() || script.content.is_some())
+            .map(|script| script.to_string())
             .collect::<Vec<String>>()
             .join("\n");
 
-        format!("<head><title>{}</title>{}</head>", self.title, scripts)
+        let style = match sheet.is_empty() {
+            true => String::new(),
+            false => format!("<style>{}</style>", sheet.to_string())
+        };
+
+        format!(
+            r#"<head><meta charset="{}"><title>{}</title>{}{}{}{}</head>"#,
+            escape_attr(&self.charset),
+            escape_text(&self.title),
+            meta,
+            links,
+            style,
+            scripts
+        )
     }
 }
 
-impl From<Value> for Head {
-    fn from(value: Value) -> Self {
-        match value {
-            Value::Obj(obj) => {
-                let mut head = Head::default();
+/// ```text
+/// Head {
+///     title: "Todo"
+///     viewport: "width=device-width, initial-scale=1"
+///     description: "Things to do"
+///     og: { title: "Todo", image: "/og.png" }
+///     meta: [{ name: "author", content: "me" }]
+///     icon: "/favicon.ico"
+///     stylesheets: ["/app.css"]
+///     links: [{ rel: "preconnect", href: "https://cdn.example.com" }]
+///     scripts: ["/analytics.js", { src: "/app.js", defer: true, module: true }]
+/// }
+/// ```
+impl TryFrom<Value> for Head {
+    type Error = HtmlError;
 
-                for prop in &obj.props {
-                    match prop.name.as_ref() {
-                        "title" => {
-                            if let Value::Str(s) = &prop.value {
-                                head.title = s.to_string();
-                            }
-                        },
-                        _ => todo!()
-                    }
-                }
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let obj = match value {
+            Value::Obj(obj) => obj,
+            value => return Err(HtmlError::new(format!("head must be a Head object, got {:?}", value)))
+        };
+
+        let mut head = Head::default();
+
+        for prop in &obj.props {
+            head.set(&prop.name, &prop.value)
+                .map_err(|err| err.in_prop(&prop.name))?;
+        }
+
+        Ok(head)
+    }
+}
+
+impl Head {
+    fn set(&mut self, name: &str, value: &Value) -> Result<(), HtmlError> {
+        let str = match value {
+            Value::Str(s) => Some(s.as_str()),
+            _ => None
+        };
 
-                head
+        match (name, str) {
+            ("title", Some(s)) => self.title = s.to_string(),
+            ("charset", Some(s)) => self.charset = s.to_string(),
+            ("viewport", Some(s)) | ("description", Some(s)) => {
+                self.meta.push(vec![attr("name", name), attr("content", s)]);
             },
-            _ => todo!()
+            ("icon", Some(s)) => {
+                self.links.push(vec![attr("rel", "icon"), attr("href", s)]);
+            },
+            ("meta", _) => self.meta.extend(list_items(value, tag_attrs)?),
+            ("links", _) => self.links.extend(list_items(value, tag_attrs)?),
+            ("stylesheets", _) => {
+                let links = list_items(value, |item| match item {
+                    Value::Str(href) => Ok(vec![attr("rel", "stylesheet"), attr("href", href)]),
+                    v => Err(HtmlError::new(format!("stylesheet must be a url, got {:?}", v)))
+                })?;
+
+                self.links.extend(links);
+            },
+            ("scripts", _) => self.scripts.extend(list_items(value, |item| Script::try_from(item))?),
+            // Open Graph names keep their underscores, `og:site_name`.
+            ("og", _) => {
+                let og = match value {
+                    Value::Obj(og) => og,
+                    v => return Err(HtmlError::new(format!("og must be an object, got {:?}", v)))
+                };
+
+                for p in &og.props {
+                    let content = match &p.value {
+                        Value::Str(s) => s.to_string(),
+                        Value::Int(i) => i.to_string(),
+                        v => return Err(HtmlError::new(format!("invalid og property {:?}", v)).in_prop(&p.name))
+                    };
+
+                    self.meta.push(vec![attr("property", &format!("og:{}", p.name)), attr("content", &content)]);
+                }
+            },
+            (name, _) if HEAD_PROPS.contains(&name) => {
+                return Err(HtmlError::new(format!("{} must be a string, got {:?}", name, value)));
+            },
+            (name, _) => return Err(HtmlError::new(format!("unknown Head property {}", name)))
         }
+
+        Ok(())
     }
 }
 
@@ -161,30 +530,195 @@ pub enum HtmlElType {
     H5,
     H6,
     Div,
+    Span,
+    P,
+    A,
+    Img,
+    Ul,
+    Ol,
+    Li,
+    Table,
+    Thead,
+    Tbody,
+    Tr,
+    Th,
+    Td,
     Body,
     Button,
     Input,
+    Form,
+    Label,
+    Select,
+    Option,
+    Textarea,
+    Nav,
+    Header,
+    Footer,
+    Section,
+    Pre,
+    Code,
     Head
 }
 
+impl HtmlElType {
+    /// Element type for a FlexScript object name, `Div` for `Div { .. }`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "H1" => Some(HtmlElType::H1),
+            "H2" => Some(HtmlElType::H2),
+            "H3" => Some(HtmlElType::H3),
+            "H4" => Some(HtmlElType::H4),
+            "H5" => Some(HtmlElType::H5),
+            "H6" => Some(HtmlElType::H6),
+            "Div" => Some(HtmlElType::Div),
+            "Span" => Some(HtmlElType::Span),
+            "P" => Some(HtmlElType::P),
+            "A" => Some(HtmlElType::A),
+            "Img" => Some(HtmlElType::Img),
+            "Ul" => Some(HtmlElType::Ul),
+            "Ol" => Some(HtmlElType::Ol),
+            "Li" => Some(HtmlElType::Li),
+            "Table" => Some(HtmlElType::Table),
+            "Thead" => Some(HtmlElType::Thead),
+            "Tbody" => Some(HtmlElType::Tbody),
+            "Tr" => Some(HtmlElType::Tr),
+            "Th" => Some(HtmlElType::Th),
+            "Td" => Some(HtmlElType::Td),
+            "Button" => Some(HtmlElType::Button),
+            "Input" => Some(HtmlElType::Input),
+            "Form" => Some(HtmlElType::Form),
+            "Label" => Some(HtmlElType::Label),
+            "Select" => Some(HtmlElType::Select),
+            "Option" => Some(HtmlElType::Option),
+            "Textarea" => Some(HtmlElType::Textarea),
+            "Nav" => Some(HtmlElType::Nav),
+            "Header" => Some(HtmlElType::Header),
+            "Footer" => Some(HtmlElType::Footer),
+            "Section" => Some(HtmlElType::Section),
+            "Pre" => Some(HtmlElType::Pre),
+            "Code" => Some(HtmlElType::Code),
+            "Head" => Some(HtmlElType::Head),
+            _ => None
+        }
+    }
+
+    /// Tag name in html.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            HtmlElType::H1 => "h1",
+            HtmlElType::H2 => "h2",
+            HtmlElType::H3 => "h3",
+            HtmlElType::H4 => "h4",
+            HtmlElType::H5 => "h5",
+            HtmlElType::H6 => "h6",
+            HtmlElType::Div => "div",
+            HtmlElType::Span => "span",
+            HtmlElType::P => "p",
+            HtmlElType::A => "a",
+            HtmlElType::Img => "img",
+            HtmlElType::Ul => "ul",
+            HtmlElType::Ol => "ol",
+            HtmlElType::Li => "li",
+            HtmlElType::Table => "table",
+            HtmlElType::Thead => "thead",
+            HtmlElType::Tbody => "tbody",
+            HtmlElType::Tr => "tr",
+            HtmlElType::Th => "th",
+            HtmlElType::Td => "td",
+            HtmlElType::Body => "body",
+            HtmlElType::Button => "button",
+            HtmlElType::Input => "input",
+            HtmlElType::Form => "form",
+            HtmlElType::Label => "label",
+            HtmlElType::Select => "select",
+            HtmlElType::Option => "option",
+            HtmlElType::Textarea => "textarea",
+            HtmlElType::Nav => "nav",
+            HtmlElType::Header => "header",
+            HtmlElType::Footer => "footer",
+            HtmlElType::Section => "section",
+            HtmlElType::Pre => "pre",
+            HtmlElType::Code => "code",
+            HtmlElType::Head => "head"
+        }
+    }
+
+    /// Elements without content or a closing tag, like `<input>`.
+    pub fn is_void(&self) -> bool {
+        matches!(self, HtmlElType::Input | HtmlElType::Img)
+    }
+
+    /// Elements whose content is shown with its whitespace, so children
+    /// are not put on separate lines.
+    pub fn keeps_whitespace(&self) -> bool {
+        matches!(self, HtmlElType::Pre | HtmlElType::Textarea)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Child {
     HtmlEl(HtmlEl),
-    Text(String)
+    Text(String),
+    /// Trusted html inserted as is, see `RAW_HTML`.
+    Raw(String)
+}
+
+impl Child {
+    pub fn render(&self, sheet: &mut StyleSheet) -> String {
+        match self {
+            Child::HtmlEl(el) => el.render(sheet),
+            Child::Text(s) => escape_text(s),
+            Child::Raw(s) => s.clone()
+        }
+    }
 }
 
 impl ToString for Child {
     fn to_string(&self) -> String {
         match self {
             Child::HtmlEl(el) => el.to_string(),
-            Child::Text(s) => s.clone()
+            child => child.render(&mut StyleSheet::default())
         }
     }
 }
 
-#[derive(Debug, Clone)]
+impl TryFrom<Value> for Child {
+    type Error = HtmlError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Str(s) => Ok(Child::Text(s)),
+            Value::Int(i) => Ok(Child::Text(i.to_string())),
+            Value::Float(f) => Ok(Child::Text(f.to_string())),
+            Value::Obj(obj) if obj.name.as_deref() == Some(RAW_HTML) => {
+                let html = match obj.props.iter().find(|p| p.name == "html").map(|p| &p.value) {
+                    Some(Value::Str(s)) => s.clone(),
+                    Some(v) => {
+                        let err = HtmlError::new(format!("html must be a string, got {:?}", v));
+                        return Err(err.in_prop("html"));
+                    },
+                    None => String::new()
+                };
+
+                Ok(Child::Raw(html))
+            },
+            value @ (Value::Obj(_) | Value::List(_)) => Ok(Child::HtmlEl(HtmlEl::try_from(value)?)),
+            value => Err(HtmlError::new(format!("{:?} can not be rendered", value)))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Display {
+    None,
+    Block,
+    Inline,
+    InlineBlock,
     Flex,
+    InlineFlex,
+    Grid,
+    InlineGrid,
+    Contents,
     Initial
 }
 
@@ -207,13 +741,39 @@ impl TryFrom<&String> for Display {
 
     fn try_from(value: &String) -> Result<Self, Self::Error> {
         match value.as_str() {
+            "none" => Ok(Display::None),
+            "block" => Ok(Display::Block),
+            "inline" => Ok(Display::Inline),
+            "inline-block" => Ok(Display::InlineBlock),
             "flex" => Ok(Display::Flex),
+            "inline-flex" => Ok(Display::InlineFlex),
+            "grid" => Ok(Display::Grid),
+            "inline-grid" => Ok(Display::InlineGrid),
+            "contents" => Ok(Display::Contents),
+            "initial" => Ok(Display::Initial),
             _ => Err(())
         }
     }
 }
 
-#[derive(Debug, Clone)]
+impl ToString for Display {
+    fn to_string(&self) -> String {
+        match self {
+            Display::None => "none",
+            Display::Block => "block",
+            Display::Inline => "inline",
+            Display::InlineBlock => "inline-block",
+            Display::Flex => "flex",
+            Display::InlineFlex => "inline-flex",
+            Display::Grid => "grid",
+            Display::InlineGrid => "inline-grid",
+            Display::Contents => "contents",
+            Display::Initial => "initial"
+        }.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum FlexDirection {
     Row,
     Column,
@@ -242,203 +802,1015 @@ impl TryFrom<&String> for FlexDirection {
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct CSSProps {
-    display: Display,
-    flex_direction: FlexDirection
+impl ToString for FlexDirection {
+    fn to_string(&self) -> String {
+        match self {
+            FlexDirection::Row => "row",
+            FlexDirection::Column => "column",
+            FlexDirection::RowReserve => "row-reverse",
+            FlexDirection::ColumnReserve => "column-reverse",
+            FlexDirection::None => ""
+        }.to_string()
+    }
 }
 
-impl ToString for CSSProps {
-    fn to_string(&self) -> String {
-        let mut s = String::new();
+/// Size with its unit. Numbers in FlexScript are pixels, `padding: 8` is
+/// `padding: 8px`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Length {
+    Zero,
+    Auto,
+    Px(f64),
+    Em(f64),
+    Rem(f64),
+    Ex(f64),
+    Ch(f64),
+    Percent(f64),
+    Vw(f64),
+    Vh(f64),
+    Vmin(f64),
+    Vmax(f64),
+    Dvw(f64),
+    Dvh(f64),
+    /// `none`, `min-content`, `max-content` and `fit-content`.
+    Keyword(String),
+    /// `calc(..)`, `min(..)`, `max(..)`, `clamp(..)` and
+    /// `fit-content(..)`, passed on as is.
+    Calc(String)
+}
 
-        match self.display {
-            Display::Flex => {
-                s.push_str("display: flex;");
-            },
-            _ => {}
+const LENGTH_KEYWORDS: &[&str] = &["none", "min-content", "max-content", "fit-content"];
+const LENGTH_FUNCTIONS: &[&str] = &["calc(", "min(", "max(", "clamp(", "fit-content("];
+
+impl TryFrom<&str> for Length {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value == "0" {
+            return Ok(Length::Zero);
         }
 
-        match self.flex_direction {
-            FlexDirection::Row => {
-                s.push_str("flex-direction: row;");
-            },
-            FlexDirection::Column => {
-                s.push_str("flex-direction: column;");
-            },
-            FlexDirection::RowReserve => {
-                s.push_str("flex-direction: row-reverse;");
-            },
-            FlexDirection::ColumnReserve => {
-                s.push_str("flex-direction: column-reverse;");
-            },
-            _ => {}
+        if value == "auto" {
+            return Ok(Length::Auto);
         }
 
-        s
-    }
-}
+        if LENGTH_KEYWORDS.contains(&value) {
+            return Ok(Length::Keyword(value.to_string()));
+        }
 
-#[derive(Debug, Clone)]
-pub struct  HtmlEl {
-    pub typ: HtmlElType,
-    pub style: CSSProps,
-    pub children: Vec<Child>
+        if LENGTH_FUNCTIONS.iter().any(|f| value.starts_with(f)) && value.ends_with(')') {
+            return Ok(Length::Calc(value.to_string()));
+        }
+
+        // Longest units first, `rem` also ends with `em` and `dvh` with
+        // `vh`.
+        let units: [(&str, fn(f64) -> Length); 12] = [
+            ("vmin", Length::Vmin),
+            ("vmax", Length::Vmax),
+            ("rem", Length::Rem),
+            ("dvw", Length::Dvw),
+            ("dvh", Length::Dvh),
+            ("px", Length::Px),
+            ("em", Length::Em),
+            ("ex", Length::Ex),
+            ("ch", Length::Ch),
+            ("vw", Length::Vw),
+            ("vh", Length::Vh),
+            ("%", Length::Percent)
+        ];
+
+        for (unit, length) in units {
+            if let Some(n) = value.strip_suffix(unit) {
+                return n.parse::<f64>().map(length).map_err(|_| ());
+            }
+        }
+
+        Err(())
+    }
 }
 
-impl ToString for HtmlEl {
+impl ToString for Length {
     fn to_string(&self) -> String {
-        let children = self.children.iter()
-            .map(|child| child.to_string())
-            .collect::<Vec<String>>().join("\n");
+        match self {
+            Length::Zero => "0".to_string(),
+            Length::Auto => "auto".to_string(),
+            Length::Px(n) => format!("{}px", n),
+            Length::Em(n) => format!("{}em", n),
+            Length::Rem(n) => format!("{}rem", n),
+            Length::Ex(n) => format!("{}ex", n),
+            Length::Ch(n) => format!("{}ch", n),
+            Length::Percent(n) => format!("{}%", n),
+            Length::Vw(n) => format!("{}vw", n),
+            Length::Vh(n) => format!("{}vh", n),
+            Length::Vmin(n) => format!("{}vmin", n),
+            Length::Vmax(n) => format!("{}vmax", n),
+            Length::Dvw(n) => format!("{}dvw", n),
+            Length::Dvh(n) => format!("{}dvh", n),
+            Length::Keyword(s) | Length::Calc(s) => s.clone()
+        }
+    }
+}
 
-        let style = self.style.to_string();
+/// Value of a style property, checked against what the property takes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CSSValue {
+    Display(Display),
+    FlexDirection(FlexDirection),
+    /// One length, or up to four for shorthands like `margin`.
+    Lengths(Vec<Length>),
+    Color(String),
+    Keyword(String),
+    Number(f64),
+    /// Not checked, for properties with a syntax of their own like
+    /// `gridTemplateColumns`, for unknown properties and for values that
+    /// are not understood.
+    Raw(String)
+}
 
-        match self.typ {
-            HtmlElType::H1 => format!("<h1>{}</h1>", children),
-            HtmlElType::H2 => format!("<h2>{}</h2>", children),
-            HtmlElType::H3 => format!("<h3>{}</h3>", children),
-            HtmlElType::H4 => format!("<h4>{}</h4>", children),
-            HtmlElType::H5 => format!("<h5>{}</h5>", children),
-            HtmlElType::H6 => format!("<h6>{}</h6>", children),
-            HtmlElType::Div => format!(r#"<div style="{}">{}</div>"#, style, children),
-            HtmlElType::Body => format!("<body>{}</body>", children),
-            HtmlElType::Button => format!("<button>{}</button>", children),
-            HtmlElType::Input => format!("<input>{}</input>", children),
-            HtmlElType::Head => format!("<head>{}</head>", children),
+impl ToString for CSSValue {
+    fn to_string(&self) -> String {
+        match self {
+            CSSValue::Display(display) => display.to_string(),
+            CSSValue::FlexDirection(direction) => direction.to_string(),
+            CSSValue::Lengths(lengths) => lengths.iter()
+                .map(|l| l.to_string())
+                .collect::<Vec<String>>()
+                .join(" "),
+            CSSValue::Color(s) | CSSValue::Keyword(s) | CSSValue::Raw(s) => s.clone(),
+            CSSValue::Number(n) => n.to_string()
         }
     }
 }
 
-impl From<Value> for HtmlEl {
-    fn from(value: Value) -> Self {
-        match value {
-            Value::Obj(obj) => {
-                let name = obj.name.unwrap();
-                let typ = match name.as_str() {
-                    "H1" => HtmlElType::H1,
-                    "H2" => HtmlElType::H2,
-                    "H3" => HtmlElType::H3,
-                    "H4" => HtmlElType::H4,
-                    "H5" => HtmlElType::H5,
-                    "H6" => HtmlElType::H6,
-                    "Button" => HtmlElType::Button,
-                    "Div" => HtmlElType::Div,
-                    "Input" => HtmlElType::Input,
-                    "Head" => HtmlElType::Head,
-                    _ => todo!("{:?}", name)
-                };
+/// What a property takes, see `property_kind`.
+enum PropertyKind {
+    Display,
+    FlexDirection,
+    Length,
+    /// Shorthand of one to four lengths, like `padding: "4px 8px"`.
+    Lengths,
+    Color,
+    Keyword(&'static [&'static str]),
+    Number,
+    FontWeight,
+    LineHeight,
+    /// `border: "1px solid #ccc"`
+    Border,
+    Raw
+}
 
+const POSITION: &[&str] = &["static", "relative", "absolute", "fixed", "sticky"];
+const FLEX_WRAP: &[&str] = &["nowrap", "wrap", "wrap-reverse"];
+const JUSTIFY: &[&str] = &["normal", "flex-start", "flex-end", "start", "end", "left", "right", "center", "space-between", "space-around", "space-evenly", "stretch"];
+const ALIGN: &[&str] = &["normal", "auto", "flex-start", "flex-end", "start", "end", "self-start", "self-end", "center", "baseline", "stretch", "space-between", "space-around", "space-evenly"];
+const TEXT_ALIGN: &[&str] = &["left", "right", "center", "justify", "start", "end"];
+const TEXT_DECORATION: &[&str] = &["none", "underline", "overline", "line-through"];
+const TEXT_TRANSFORM: &[&str] = &["none", "capitalize", "uppercase", "lowercase"];
+const FONT_STYLE: &[&str] = &["normal", "italic", "oblique"];
+const FONT_WEIGHT: &[&str] = &["normal", "bold", "lighter", "bolder"];
+const BORDER_STYLE: &[&str] = &["none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset"];
+const OVERFLOW: &[&str] = &["visible", "hidden", "clip", "scroll", "auto"];
+const VISIBILITY: &[&str] = &["visible", "hidden", "collapse"];
+const WHITE_SPACE: &[&str] = &["normal", "nowrap", "pre", "pre-wrap", "pre-line", "break-spaces"];
+const BOX_SIZING: &[&str] = &["content-box", "border-box"];
+const CSS_WIDE: &[&str] = &["inherit", "initial", "unset", "revert"];
 
-                let mut el = HtmlEl {
-                    typ: typ,
-                    style: CSSProps::default(),
-                    children: vec![]
-                };
+/// Kind of the value property `name` takes, by its FlexScript name.
+/// `None` for properties that are passed on unchecked.
+fn property_kind(name: &str) -> Option<PropertyKind> {
+    let kind = match name {
+        "display" => PropertyKind::Display,
+        "flexDirection" => PropertyKind::FlexDirection,
+        "width" | "height" | "minWidth" | "minHeight" | "maxWidth" | "maxHeight" |
+        "top" | "right" | "bottom" | "left" |
+        "marginTop" | "marginRight" | "marginBottom" | "marginLeft" |
+        "paddingTop" | "paddingRight" | "paddingBottom" | "paddingLeft" |
+        "gap" | "rowGap" | "columnGap" | "flexBasis" |
+        "fontSize" | "letterSpacing" | "borderWidth" | "outlineWidth" => PropertyKind::Length,
+        "margin" | "padding" | "inset" | "borderRadius" => PropertyKind::Lengths,
+        "color" | "backgroundColor" | "borderColor" | "outlineColor" => PropertyKind::Color,
+        "position" => PropertyKind::Keyword(POSITION),
+        "flexWrap" => PropertyKind::Keyword(FLEX_WRAP),
+        "justifyContent" | "justifyItems" | "justifySelf" => PropertyKind::Keyword(JUSTIFY),
+        "alignItems" | "alignContent" | "alignSelf" => PropertyKind::Keyword(ALIGN),
+        "textAlign" => PropertyKind::Keyword(TEXT_ALIGN),
+        "textDecoration" => PropertyKind::Keyword(TEXT_DECORATION),
+        "textTransform" => PropertyKind::Keyword(TEXT_TRANSFORM),
+        "fontStyle" => PropertyKind::Keyword(FONT_STYLE),
+        "borderStyle" | "outlineStyle" => PropertyKind::Keyword(BORDER_STYLE),
+        "overflow" | "overflowX" | "overflowY" => PropertyKind::Keyword(OVERFLOW),
+        "visibility" => PropertyKind::Keyword(VISIBILITY),
+        "whiteSpace" => PropertyKind::Keyword(WHITE_SPACE),
+        "boxSizing" => PropertyKind::Keyword(BOX_SIZING),
+        "opacity" | "flexGrow" | "flexShrink" | "zIndex" | "order" => PropertyKind::Number,
+        "fontWeight" => PropertyKind::FontWeight,
+        "lineHeight" => PropertyKind::LineHeight,
+        "border" | "borderTop" | "borderRight" | "borderBottom" | "borderLeft" | "outline" => PropertyKind::Border,
+        "flex" | "fontFamily" | "background" | "cursor" | "transform" | "transition" | "boxShadow" |
+        "gridTemplateColumns" | "gridTemplateRows" | "gridTemplateAreas" | "gridColumn" | "gridRow" |
+        "gridArea" | "gridAutoFlow" | "objectFit" | "listStyle" | "content" => PropertyKind::Raw,
+        _ => return None
+    };
 
-                for prop in &obj.props {
-                    match prop.name.as_ref() {
-                        "type" => {
-                            
-                        },
-                        "children" => {
-                            if let Value::List(list) = &prop.value {
-                                for item in list {
-                                    if let Value::Str(s) = item {
-                                        el.children.push(Child::Text(s.to_string()));
-                                    } else {
-                                        el.children.push(Child::HtmlEl(HtmlEl::from(item.clone())));
-                                    }
-                                }
-                            }
-                        },
-                        "text" => {
-                            match &prop.value {
-                                Value::Str(s) => {
-                                    el.children.push(Child::Text(s.to_string()));
-                                },
-                                Value::Int(i) => {
-                                    el.children.push(Child::Text(i.to_string()));
-                                },
-                                _ => todo!("{:?}", prop)
-                            }
-                        },
-                        "title" => {},
-                        "style" => {
-                            match &prop.value {
-                                Value::Obj(obj) => {
-                                    for prop in &obj.props {
-                                        match prop.name.as_ref() {
-                                            "display" => {
-                                                match &prop.value {
-                                                    Value::Str(str) => {
-                                                        el.style.display = Display::try_from(str).unwrap()
-                                                    },
-                                                    _ => todo!("{:?}", prop)
-                                                }
-                                                
-                                            },
-                                            "flexDirection" => {
-                                                
-                                            },
-                                            _ => todo!("{:?}", prop)
-                                        }
-                                    }
-                                },
-                                _ => todo!("{:?}", prop)
-                            }
-                        }
-                        _ => {}
-                    }
-                }
+    Some(kind)
+}
 
-                el
-            },
-            Value::List(list) => {
-                let mut el = HtmlEl {
-                    typ: HtmlElType::Div,
-                    style: CSSProps::default(),
-                    children: vec![]
-                };
+fn is_color(value: &str) -> bool {
+    if let Some(hex) = value.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
 
-                for item in list {
-                    if let Value::Str(s) = item {
-                        el.children.push(Child::Text(s.to_string()));
-                    } else {
-                        el.children.push(Child::HtmlEl(HtmlEl::from(item.clone())));
-                    }
-                }
+    let functions = [
+        "rgb(", "rgba(", "hsl(", "hsla(", "hwb(", "lab(", "lch(", "oklab(", "oklch(",
+        "color(", "color-mix("
+    ];
 
-                el
-            },
-            _ => todo!("{:?}", value)
-        }
+    if functions.iter().any(|f| value.starts_with(f)) {
+        return value.ends_with(')');
     }
+
+    // Named colors like `rebeccapurple`, `transparent` and `currentcolor`.
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_alphabetic())
 }
 
-#[cfg(test)]
-mod test {
-    use flexscript::RunResult;
-    use flexscript::Vm;
+/// Pseudo-class a style object key stands for, `hover: { .. }` for
+/// `:hover`.
+fn pseudo_class(name: &str) -> Option<&'static str> {
+    match name {
+        "hover" => Some(":hover"),
+        "focus" => Some(":focus"),
+        "focusVisible" => Some(":focus-visible"),
+        "focusWithin" => Some(":focus-within"),
+        "active" => Some(":active"),
+        "disabled" => Some(":disabled"),
+        _ => None
+    }
+}
 
-    use super::*;
+/// Smallest viewport width in pixels a breakpoint key stands for,
+/// `md: { .. }` applies from 768px on.
+fn breakpoint(name: &str) -> Option<u32> {
+    match name {
+        "sm" => Some(640),
+        "md" => Some(768),
+        "lg" => Some(1024),
+        "xl" => Some(1280),
+        _ => None
+    }
+}
 
-    #[test]
-    fn empty_html_page() {
-        let html = Html::default();
+/// When a declaration applies. The default applies always.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Condition {
+    /// Pseudo-class like `:hover`.
+    pub pseudo: Option<&'static str>,
+    /// Smallest viewport width in pixels, see `breakpoint`.
+    pub min_width: Option<u32>
+}
 
-        assert_eq!(html.to_string(), r#"<html>
-<head>
-<title></title>
-</head>
-<body>
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub condition: Condition,
+    /// Css name like `flex-direction`.
+    pub name: String,
+    pub value: CSSValue
+}
 
-</body>
-</html>"#);
+impl ToString for Declaration {
+    fn to_string(&self) -> String {
+        format!("{}: {};", self.name, self.value.to_string())
     }
+}
 
-    #[test]
-    fn html_page_from_value() {
+#[derive(Debug, Clone, Default)]
+pub struct CSSProps {
+    /// Declarations in the order they were first set.
+    declarations: Vec<Declaration>
+}
+
+impl CSSProps {
+    /// Whether `name` is a property with a checked value. Others are
+    /// accepted by `set` and passed on as they are.
+    pub fn is_known(name: &str) -> bool {
+        property_kind(name).is_some()
+    }
+
+    /// Checks a value like `set` does, except that a value which is not
+    /// understood is an error instead of being passed on, see `monolith
+    /// check`.
+    pub fn validate(name: &str, value: &Value) -> Result<(), String> {
+        parse_value(name, value).map(|_| ())
+    }
+
+    /// Whether `name` is a pseudo-class or breakpoint key, whose value is an
+    /// object of properties.
+    pub fn is_variant(name: &str) -> bool {
+        pseudo_class(name).is_some() || breakpoint(name).is_some()
+    }
+
+    /// Value of a property by its FlexScript name, without a pseudo-class
+    /// or breakpoint.
+    pub fn get(&self, name: &str) -> Option<&CSSValue> {
+        let css_name = kebab_case(name);
+
+        self.declarations.iter()
+            .find(|d| d.condition == Condition::default() && d.name == css_name)
+            .map(|d| &d.value)
+    }
+
+    pub fn declarations(&self) -> &[Declaration] {
+        &self.declarations
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.declarations.is_empty()
+    }
+
+    /// Sets a style property by its FlexScript name, like `flexDirection`.
+    /// Pseudo-class and breakpoint keys take an object of properties:
+    /// `hover: { color: "red" }`, `md: { display: "flex" }`. A breakpoint
+    /// may contain pseudo-classes and the other way around. `margin`,
+    /// `padding`, `inset` and `borderRadius` are set per side, other
+    /// shorthands can not be combined with their longhands. Strings that
+    /// are not understood are passed on as they are, see `validate`.
+    pub fn set(&mut self, name: &str, value: &Value) -> Result<(), String> {
+        self.set_in(Condition::default(), name, value)
+    }
+
+    fn set_in(&mut self, condition: Condition, name: &str, value: &Value) -> Result<(), String> {
+        let nested = match (pseudo_class(name), breakpoint(name)) {
+            (Some(pseudo), _) if condition.pseudo.is_none() => Some(Condition {
+                pseudo: Some(pseudo),
+                ..condition
+            }),
+            (_, Some(width)) if condition.min_width.is_none() => Some(Condition {
+                min_width: Some(width),
+                ..condition
+            }),
+            (Some(_), _) | (_, Some(_)) => return Err(format!("style {} can not be nested here", name)),
+            _ => None
+        };
+
+        if let Some(nested) = nested {
+            let obj = match value {
+                Value::Obj(obj) => obj,
+                _ => return Err(format!("style {} must be an object, got {:?}", name, value))
+            };
+
+            for prop in &obj.props {
+                self.set_in(nested, &prop.name, &prop.value)?;
+            }
+
+            return Ok(());
+        }
+
+        let value = parse_value_or_raw(name, value)?;
+
+        for (name, value) in expand(&kebab_case(name), value) {
+            self.push(Declaration {
+                condition: condition,
+                name: name,
+                value: value
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds `declaration` or replaces the one with the same name. A rule
+    /// per declaration loses the order they were set in, so a shorthand
+    /// can not be used together with one of its longhands.
+    fn push(&mut self, declaration: Declaration) -> Result<(), String> {
+        let covered = longhands(&declaration.name);
+
+        let overlapping = self.declarations.iter().find(|d| {
+            d.condition == declaration.condition &&
+                d.name != declaration.name &&
+                longhands(&d.name).iter().any(|l| covered.contains(l))
+        });
+
+        if let Some(other) = overlapping {
+            return Err(format!("style {} overlaps {}, use only one of them", declaration.name, other.name));
+        }
+
+        let existing = self.declarations.iter_mut()
+            .find(|d| d.condition == declaration.condition && d.name == declaration.name);
+
+        match existing {
+            Some(existing) => *existing = declaration,
+            None => self.declarations.push(declaration)
+        }
+
+        Ok(())
+    }
+}
+
+const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+const CORNERS: [&str; 4] = ["top-left", "top-right", "bottom-right", "bottom-left"];
+
+/// Longhands of the four sides or corners a box shorthand like `padding`
+/// sets, in the order its values are given.
+fn box_longhands(name: &str) -> Option<Vec<String>> {
+    let longhands = match name {
+        "margin" | "padding" => SIDES.iter().map(|side| format!("{}-{}", name, side)).collect(),
+        "inset" => SIDES.iter().map(|side| side.to_string()).collect(),
+        "border-radius" => CORNERS.iter().map(|corner| format!("border-{}-radius", corner)).collect(),
+        _ => return None
+    };
+
+    Some(longhands)
+}
+
+/// Splits box shorthands into a declaration per side, so `padding: 4px 8px`
+/// becomes `padding-top: 4px`, `padding-right: 8px` and so on. Values that
+/// can not be split, like `var(--space)`, stay a shorthand.
+fn expand(name: &str, value: CSSValue) -> Vec<(String, CSSValue)> {
+    let longhands = match box_longhands(name) {
+        Some(longhands) => longhands,
+        None => return vec![(name.to_string(), value)]
+    };
+
+    let values = match &value {
+        // One value for all sides, two for top and bottom and left and
+        // right, three for top, left and right and bottom.
+        CSSValue::Lengths(lengths) => {
+            let inxs = match lengths.len() {
+                1 => [0, 0, 0, 0],
+                2 => [0, 1, 0, 1],
+                3 => [0, 1, 2, 1],
+                _ => [0, 1, 2, 3]
+            };
+
+            inxs.iter()
+                .map(|inx| CSSValue::Lengths(vec![lengths[*inx].clone()]))
+                .collect()
+        },
+        CSSValue::Keyword(keyword) if CSS_WIDE.contains(&keyword.as_str()) => vec![value.clone(); 4],
+        _ => return vec![(name.to_string(), value)]
+    };
+
+    longhands.into_iter().zip(values).collect()
+}
+
+/// Css properties that `name` sets, itself for a longhand.
+fn longhands(name: &str) -> Vec<String> {
+    let parts = |prefix: &str, names: &[&str]| -> Vec<String> {
+        names.iter().map(|part| format!("{}-{}", prefix, part)).collect()
+    };
+
+    if let Some(longhands) = box_longhands(name) {
+        return longhands;
+    }
+
+    match name {
+        "border" => SIDES.iter()
+            .flat_map(|side| parts(&format!("border-{}", side), &["width", "style", "color"]))
+            .collect(),
+        "border-top" | "border-right" | "border-bottom" | "border-left" | "outline" => {
+            parts(name, &["width", "style", "color"])
+        },
+        "border-width" | "border-style" | "border-color" => {
+            let part = name.trim_start_matches("border-");
+
+            SIDES.iter().map(|side| format!("border-{}-{}", side, part)).collect()
+        },
+        "flex" => parts("flex", &["grow", "shrink", "basis"]),
+        "gap" => vec!["row-gap".to_string(), "column-gap".to_string()],
+        "overflow" => parts("overflow", &["x", "y"]),
+        "background" => vec!["background-color".to_string()],
+        "grid-area" => ["grid-row", "grid-column"].iter()
+            .flat_map(|n| parts(n, &["start", "end"]))
+            .collect(),
+        "grid-row" | "grid-column" => parts(name, &["start", "end"]),
+        _ => vec![name.to_string()]
+    }
+}
+
+/// Like `parse_value`, but a string that is not understood is passed on
+/// as it is when it can not end its declaration. The browser may know
+/// syntax this does not.
+fn parse_value_or_raw(name: &str, value: &Value) -> Result<CSSValue, String> {
+    parse_value(name, value).or_else(|err| match value {
+        Value::Str(str) if !ends_declaration(str) => {
+            log::warn!("style {}: {}, passed on as is", name, err);
+            Ok(CSSValue::Raw(str.trim().to_string()))
+        },
+        _ => Err(err)
+    })
+}
+
+/// Whether `value` would end the declaration or the rule it is in.
+fn ends_declaration(value: &str) -> bool {
+    value.contains(|c: char| matches!(c, ';' | '{' | '}' | '<'))
+}
+
+/// Checks `value` against what property `name` takes.
+fn parse_value(name: &str, value: &Value) -> Result<CSSValue, String> {
+    let str = match value {
+        Value::Str(str) => str.trim().to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        _ => return Err(format!("style {} must be a string or a number, got {:?}", name, value))
+    };
+
+    if ends_declaration(&str) {
+        return Err(format!("invalid {} {:?}", name, str));
+    }
+
+    let invalid = || format!("invalid {} {:?}", name, str);
+    let is_number = matches!(value, Value::Int(_) | Value::Float(_));
+
+    let css_value = if CSS_WIDE.contains(&str.as_str()) || str.starts_with("var(") {
+        CSSValue::Keyword(str)
+    } else {
+        match property_kind(name) {
+            Some(PropertyKind::Display) => {
+                CSSValue::Display(Display::try_from(&str).map_err(|_| invalid())?)
+            },
+            Some(PropertyKind::FlexDirection) => {
+                CSSValue::FlexDirection(FlexDirection::try_from(&str).map_err(|_| invalid())?)
+            },
+            Some(PropertyKind::Length) => {
+                CSSValue::Lengths(vec![parse_length(&str, is_number).ok_or_else(invalid)?])
+            },
+            Some(PropertyKind::Lengths) => {
+                let lengths = split_values(&str).iter()
+                    .map(|s| parse_length(s, is_number))
+                    .collect::<Option<Vec<Length>>>()
+                    .filter(|l| (1..=4).contains(&l.len()))
+                    .ok_or_else(invalid)?;
+
+                CSSValue::Lengths(lengths)
+            },
+            Some(PropertyKind::Color) => {
+                if !is_color(&str) {
+                    return Err(invalid());
+                }
+
+                CSSValue::Color(str)
+            },
+            Some(PropertyKind::Keyword(keywords)) => {
+                if !keywords.contains(&str.as_str()) {
+                    return Err(invalid());
+                }
+
+                CSSValue::Keyword(str)
+            },
+            Some(PropertyKind::Number) => {
+                CSSValue::Number(str.parse().map_err(|_| invalid())?)
+            },
+            Some(PropertyKind::FontWeight) => {
+                match str.parse::<f64>() {
+                    Ok(n) if (1.0..=1000.0).contains(&n) => CSSValue::Number(n),
+                    Err(_) if FONT_WEIGHT.contains(&str.as_str()) => CSSValue::Keyword(str),
+                    _ => return Err(invalid())
+                }
+            },
+            // Numbers without a unit are a multiple of the font size.
+            Some(PropertyKind::LineHeight) => match str.parse::<f64>() {
+                Ok(n) => CSSValue::Number(n),
+                Err(_) => CSSValue::Lengths(vec![parse_length(&str, false).ok_or_else(invalid)?])
+            },
+            Some(PropertyKind::Border) => {
+                let valid = str == "none" || split_values(&str).iter().all(|part| {
+                    parse_length(part, is_number).is_some() ||
+                        BORDER_STYLE.contains(&part.as_str()) ||
+                        is_color(part)
+                });
+
+                if !valid {
+                    return Err(invalid());
+                }
+
+                CSSValue::Raw(str)
+            },
+            Some(PropertyKind::Raw) | None => CSSValue::Raw(str)
+        }
+    };
+
+    Ok(css_value)
+}
+
+/// Rules of the classes the styles of a page become. Every declaration is
+/// one class, so elements with the same style share their classes and the
+/// page has each rule once. The rules of an element never set the same
+/// property twice, see `CSSProps::set`, so their order does not matter.
+#[derive(Debug, Clone, Default)]
+pub struct StyleSheet {
+    rules: Vec<(String, Declaration)>
+}
+
+impl StyleSheet {
+    /// Class for `declaration`, added to the sheet when it is new. Names
+    /// are a hash of the declaration, so they stay the same between renders.
+    pub fn class(&mut self, declaration: &Declaration) -> String {
+        if let Some((class, _)) = self.rules.iter().find(|(_, d)| d == declaration) {
+            return class.clone();
+        }
+
+        let key = format!("{:?}{:?}{}", declaration.condition.pseudo, declaration.condition.min_width, declaration.to_string());
+        let mut hash = fnv1a(&key);
+        let mut class = format!("s{:08x}", hash);
+
+        while self.rules.iter().any(|(c, _)| *c == class) {
+            hash = hash.wrapping_add(1);
+            class = format!("s{:08x}", hash);
+        }
+
+        self.rules.push((class.clone(), declaration.clone()));
+
+        class
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+impl ToString for StyleSheet {
+    fn to_string(&self) -> String {
+        // Later rules win, so pseudo-classes come after plain rules and
+        // wider breakpoints after narrower ones.
+        let mut rules = self.rules.iter().collect::<Vec<_>>();
+        rules.sort_by_key(|(_, d)| (d.condition.min_width, d.condition.pseudo.is_some()));
+
+        rules.iter()
+            .map(|(class, declaration)| {
+                let rule = format!(
+                    ".{}{}{{{}}}",
+                    class,
+                    declaration.condition.pseudo.unwrap_or_default(),
+                    declaration.to_string()
+                );
+
+                match declaration.condition.min_width {
+                    Some(width) => format!("@media (min-width: {}px){{{}}}", width, rule),
+                    None => rule
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is the same in every build.
+fn fnv1a(s: &str) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+
+    for b in s.bytes() {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+
+    hash
+}
+
+/// Splits a value on whitespace that is not inside parentheses, so
+/// `1px solid rgb(0, 0, 0)` has three parts.
+fn split_values(value: &str) -> Vec<String> {
+    let mut parts = vec![];
+    let mut part = String::new();
+    let mut depth = 0;
+
+    for c in value.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                if !part.is_empty() {
+                    parts.push(std::mem::take(&mut part));
+                }
+
+                continue;
+            },
+            _ => {}
+        }
+
+        part.push(c);
+    }
+
+    if !part.is_empty() {
+        parts.push(part);
+    }
+
+    parts
+}
+
+/// Length from a style value, `number` when it was given as a FlexScript
+/// number and so is in pixels.
+fn parse_length(value: &str, number: bool) -> Option<Length> {
+    if number {
+        return match value.parse::<f64>() {
+            Ok(n) if n == 0.0 => Some(Length::Zero),
+            Ok(n) => Some(Length::Px(n)),
+            Err(_) => None
+        };
+    }
+
+    Length::try_from(value).ok()
+}
+
+/// Inline form of the declarations that apply always. Pseudo-classes and
+/// breakpoints need a `StyleSheet`.
+impl ToString for CSSProps {
+    fn to_string(&self) -> String {
+        self.declarations.iter()
+            .filter(|d| d.condition == Condition::default())
+            .map(|d| d.to_string())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct  HtmlEl {
+    pub typ: HtmlElType,
+    pub style: CSSProps,
+    /// Attributes by name. `None` is a boolean attribute that is present,
+    /// like `checked`.
+    pub attrs: BTreeMap<String, Option<String>>,
+    pub classes: Vec<String>,
+    pub children: Vec<Child>
+}
+
+/// Renders the element on its own, with a `<style>` for its classes in
+/// front when it has a style.
+impl ToString for HtmlEl {
+    fn to_string(&self) -> String {
+        let mut sheet = StyleSheet::default();
+        let html = self.render(&mut sheet);
+
+        match sheet.is_empty() {
+            true => html,
+            false => format!("<style>{}</style>\n{}", sheet.to_string(), html)
+        }
+    }
+}
+
+impl HtmlEl {
+    /// Renders the element with its style turned into classes of `sheet`.
+    pub fn render(&self, sheet: &mut StyleSheet) -> String {
+        let tag = self.typ.tag();
+        let mut attrs = String::new();
+
+        let mut classes = self.classes.clone();
+
+        for declaration in self.style.declarations() {
+            let class = sheet.class(declaration);
+
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+
+        if !classes.is_empty() {
+            attrs.push_str(&format!(r#" class="{}""#, escape_attr(&classes.join(" "))));
+        }
+
+        for (name, value) in &self.attrs {
+            match value {
+                Some(value) => attrs.push_str(&format!(r#" {}="{}""#, name, escape_attr(value))),
+                None => attrs.push_str(&format!(" {}", name))
+            }
+        }
+
+        if self.typ.is_void() {
+            return format!("<{}{}>", tag, attrs);
+        }
+
+        let separator = match self.typ.keeps_whitespace() {
+            true => "",
+            false => "\n"
+        };
+
+        let children = self.children.iter()
+            .map(|child| child.render(sheet))
+            .collect::<Vec<String>>().join(separator);
+
+        format!("<{}{}>{}</{}>", tag, attrs, children, tag)
+    }
+
+    /// Sets attribute `name` from a FlexScript value. `true` adds a boolean
+    /// attribute, `false` and `None` leave it out.
+    pub fn set_attr(&mut self, name: &str, value: &Value) -> Result<(), HtmlError> {
+        let value = match value {
+            Value::Str(s) => Some(s.to_string()),
+            Value::Int(i) => Some(i.to_string()),
+            Value::Float(f) => Some(f.to_string()),
+            Value::Bool(true) => None,
+            Value::Bool(false) | Value::None => {
+                self.attrs.remove(name);
+                return Ok(());
+            },
+            _ => return Err(HtmlError::new(format!("invalid attribute value {:?}", value)))
+        };
+
+        self.attrs.insert(name.to_string(), value);
+
+        Ok(())
+    }
+
+    /// Adds classes from a string of names, a list of names or an object of
+    /// name to condition, like `{ done: todo.completed }`.
+    pub fn add_classes(&mut self, value: &Value) -> Result<(), HtmlError> {
+        match value {
+            Value::Str(s) => {
+                self.classes.extend(s.split_whitespace().map(|c| c.to_string()));
+            },
+            Value::List(list) => {
+                for (i, item) in list.iter().enumerate() {
+                    self.add_classes(item).map_err(|err| err.in_index(i))?;
+                }
+            },
+            Value::Obj(obj) => {
+                for prop in &obj.props {
+                    if let Value::Bool(true) = prop.value {
+                        self.classes.push(prop.name.to_string());
+                    }
+                }
+            },
+            Value::None => {},
+            _ => return Err(HtmlError::new(format!("invalid class {:?}", value)))
+        }
+
+        Ok(())
+    }
+
+    /// Sets `data-*` or `aria-*` attributes from an object, `data: { userId: 1 }`
+    /// becomes `data-user-id="1"`.
+    fn set_prefixed_attrs(&mut self, prefix: &str, value: &Value) -> Result<(), HtmlError> {
+        let obj = match value {
+            Value::Obj(obj) => obj,
+            _ => return Err(HtmlError::new(format!("{} must be an object, got {:?}", prefix, value)))
+        };
+
+        for prop in &obj.props {
+            let name = format!("{}-{}", prefix, kebab_case(&prop.name));
+
+            match &prop.value {
+                // aria states are spelled out, aria-hidden="false" is not
+                // the same as leaving it out.
+                Value::Bool(b) => {
+                    self.attrs.insert(name, Some(b.to_string()));
+                },
+                value => self.set_attr(&name, value).map_err(|err| err.in_prop(&prop.name))?
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn new(typ: HtmlElType) -> Self {
+        HtmlEl {
+            typ: typ,
+            style: CSSProps::default(),
+            attrs: BTreeMap::new(),
+            classes: vec![],
+            children: vec![]
+        }
+    }
+
+    /// Adds a list of children, or a single one. `None` items are left
+    /// out, so children can be shown conditionally.
+    pub fn add_children(&mut self, value: Value) -> Result<(), HtmlError> {
+        let list = match value {
+            Value::List(list) => list,
+            Value::None => vec![],
+            value => {
+                self.children.push(Child::try_from(value)?);
+                return Ok(());
+            }
+        };
+
+        for (i, item) in list.into_iter().enumerate() {
+            if let Value::None = item {
+                continue;
+            }
+
+            let child = Child::try_from(item).map_err(|err| err.in_index(i))?;
+            self.children.push(child);
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether attribute `name` is an inline event handler like `onclick`,
+/// whose value would run as code.
+fn is_inline_handler(name: &str) -> bool {
+    name.to_ascii_lowercase().starts_with("on")
+}
+
+/// `userId` to `user-id`.
+fn kebab_case(name: &str) -> String {
+    let mut s = String::new();
+
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if !s.is_empty() {
+                s.push('-');
+            }
+
+            s.push(c.to_ascii_lowercase());
+        } else if c == '_' {
+            s.push('-');
+        } else {
+            s.push(c);
+        }
+    }
+
+    s
+}
+
+impl TryFrom<Value> for HtmlEl {
+    type Error = HtmlError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let obj = match value {
+            Value::Obj(obj) => obj,
+            Value::List(list) => {
+                let mut el = HtmlEl::new(HtmlElType::Div);
+                el.add_children(Value::List(list))?;
+
+                return Ok(el);
+            },
+            value => return Err(HtmlError::new(format!("{:?} can not be rendered", value)))
+        };
+
+        let typ = match obj.name.as_deref() {
+            Some(name) => match HtmlElType::from_name(name) {
+                Some(typ) => typ,
+                None => return Err(HtmlError::new(format!("unknown element {}", name)))
+            },
+            None => return Err(HtmlError::new("element without a name"))
+        };
+
+        let mut el = HtmlEl::new(typ);
+
+        for prop in obj.props {
+            let name = prop.name.as_str();
+
+            let res = match name {
+                "children" => el.add_children(prop.value),
+                "text" => match prop.value {
+                    value @ (Value::Str(_) | Value::Int(_) | Value::Float(_)) => el.add_children(value),
+                    v => Err(HtmlError::new(format!("text can not be {:?}", v)))
+                },
+                "style" => match &prop.value {
+                    Value::Obj(style) => style.props.iter().try_for_each(|p| {
+                        el.style.set(&p.name, &p.value)
+                            .map_err(|err| HtmlError::new(err).in_prop(&p.name))
+                    }),
+                    v => Err(HtmlError::new(format!("style must be an object, got {:?}", v)))
+                },
+                // The value of a textarea is its text.
+                "value" if matches!(el.typ, HtmlElType::Textarea) => match prop.value {
+                    value @ (Value::Str(_) | Value::Int(_) | Value::Float(_)) => el.add_children(value),
+                    Value::None => Ok(()),
+                    v => Err(HtmlError::new(format!("value can not be {:?}", v)))
+                },
+                "class" => el.add_classes(&prop.value),
+                "data" | "aria" => el.set_prefixed_attrs(name, &prop.value),
+                // Event handlers run in the page script.
+                name if name.starts_with("on_") => Ok(()),
+                name if is_inline_handler(name) => {
+                    Err(HtmlError::new(format!("attribute {} is not allowed, use on_ handlers", name)))
+                },
+                name => el.set_attr(&name.to_ascii_lowercase(), &prop.value)
+            };
+
+            res.map_err(|err| err.in_prop(name))?;
+        }
+
+        if let HtmlElType::Form = el.typ {
+            if !el.attrs.contains_key("method") {
+                el.attrs.insert("method".to_string(), Some("post".to_string()));
+            }
+        }
+
+        Ok(el)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use flexscript::RunResult;
+    use flexscript::Vm;
+
+    use super::*;
+
+    #[test]
+    fn empty_html_page() {
+        let html = Html::default();
+
+        assert_eq!(html.to_string(), r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title></title></head>
+<body></body>
+</html>"#);
+    }
+
+    #[test]
+    fn html_page_from_value() {
         let mut vm = Vm::new();
 
         let res = vm.run_code(r#"
@@ -451,8 +1823,8 @@ mod test {
 
         match res {
             RunResult::Value(value) => {
-                let html = Html::from(value).to_string();
-                assert_eq!(html, "<html>\n<head>\n<title>hello</title>\n</head>\n<body>\n\n</body>\n</html>");
+                let html = Html::try_from(value).unwrap().to_string();
+                assert_eq!(html, "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>hello</title></head>\n<body></body>\n</html>");
             },
             _ => todo!()
         }
@@ -478,10 +1850,329 @@ mod test {
 
         match res {
             RunResult::Value(value) => {
-                let html = Html::from(value).to_string();
-                assert_eq!(html, "<html>\n<head>\n<title>hello</title>\n</head>\n<body>\n<h1>\n1\n</h1>\n<h1>\n2\n</h1>\n<h1>\n3\n</h1>\n</body>\n</html>");
+                let html = Html::try_from(value).unwrap().to_string();
+                assert_eq!(html, "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>hello</title></head>\n<body><h1>1</h1>\n<h1>2</h1>\n<h1>3</h1></body>\n</html>");
             },
             _ => todo!()
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn escapes_text_and_attributes() {
+        let el = HtmlEl {
+            typ: HtmlElType::Div,
+            style: CSSProps::default(),
+            attrs: BTreeMap::from([("title".to_string(), Some(r#"a "quoted" <value>"#.to_string()))]),
+            classes: vec![],
+            children: vec![
+                Child::Text("<script>alert(1)</script>".to_string()),
+                Child::Raw("<b>trusted</b>".to_string())
+            ]
+        };
+
+        assert_eq!(
+            el.to_string(),
+            r#"<div title="a &quot;quoted&quot; &lt;value&gt;">&lt;script&gt;alert(1)&lt;/script&gt;
+<b>trusted</b></div>"#
+        );
+    }
+
+    #[test]
+    fn escapes_inline_scripts() {
+        assert_eq!(
+            escape_script(r#"e.innerText = "</script><script>alert(1)""#),
+            r#"e.innerText = "<\/script><script>alert(1)""#
+        );
+    }
+
+    #[test]
+    fn form_posts_by_default() {
+        let mut vm = Vm::new();
+
+        let res = vm.run_code(r#"
+        return Form {
+            action: "/todo"
+            children: [
+                Input {
+                    name: "name"
+                    placeholder: "Todo"
+                }
+            ]
+        }"#);
+
+        match res {
+            RunResult::Value(value) => {
+                let html = HtmlEl::try_from(value).unwrap().to_string();
+                assert_eq!(html, r#"<form action="/todo" method="post"><input name="name" placeholder="Todo"></form>"#);
+            },
+            _ => todo!()
+        }
+    }
+
+    #[test]
+    fn renders_attributes_and_classes() {
+        let mut vm = Vm::new();
+
+        let res = vm.run_code(r#"
+        return Input {
+            type: "checkbox"
+            checked: true
+            disabled: false
+            class: ["todo", { done: true, hidden: false }]
+            data: { todoId: 3 }
+            aria: { hidden: false }
+        }"#);
+
+        match res {
+            RunResult::Value(value) => {
+                let html = HtmlEl::try_from(value).unwrap().to_string();
+                assert_eq!(html, r#"<input class="todo done" aria-hidden="false" checked data-todo-id="3" type="checkbox">"#);
+            },
+            _ => todo!()
+        }
+    }
+
+    #[test]
+    fn rejects_inline_handlers() {
+        let mut vm = Vm::new();
+
+        for code in [r#"return Img { onerror: "alert(1)" }"#, r#"return Div { onClick: "alert(1)" }"#] {
+            let value = match vm.run_code(code) {
+                RunResult::Value(value) => value,
+                _ => todo!()
+            };
+
+            assert!(HtmlEl::try_from(value).is_err(), "{}", code);
+        }
+    }
+
+    #[test]
+    fn textarea_value_is_text() {
+        let mut vm = Vm::new();
+
+        let res = vm.run_code(r#"
+        return Textarea {
+            name: "note"
+            value: "</textarea><script>"
+        }"#);
+
+        match res {
+            RunResult::Value(value) => {
+                let html = HtmlEl::try_from(value).unwrap().to_string();
+                assert_eq!(html, r#"<textarea name="note">&lt;/textarea&gt;&lt;script&gt;</textarea>"#);
+            },
+            _ => todo!()
+        }
+    }
+
+    #[test]
+    fn renders_common_elements() {
+        let mut vm = Vm::new();
+
+        let res = vm.run_code(r#"
+        return Nav {
+            children: [
+                A { href: "/about", text: "About" }
+                Img { src: "/logo.png", alt: "Logo" }
+                Ul { children: [Li { text: "one" }] }
+                Pre { children: ["a", "b"] }
+            ]
+        }"#);
+
+        match res {
+            RunResult::Value(value) => {
+                let html = HtmlEl::try_from(value).unwrap().to_string();
+                assert_eq!(html, r#"<nav><a href="/about">About</a>
+<img alt="Logo" src="/logo.png">
+<ul><li>one</li></ul>
+<pre>ab</pre></nav>"#);
+            },
+            _ => todo!()
+        }
+    }
+
+    #[test]
+    fn style_properties() {
+        let mut style = CSSProps::default();
+
+        style.set("display", &Value::Str("grid".to_string())).unwrap();
+        style.set("padding", &Value::Str("4px 1.5rem".to_string())).unwrap();
+        style.set("marginTop", &Value::Int(8)).unwrap();
+        style.set("backgroundColor", &Value::Str("#fafafa".to_string())).unwrap();
+        style.set("border", &Value::Str("1px solid rgb(0, 0, 0)".to_string())).unwrap();
+        style.set("fontWeight", &Value::Int(600)).unwrap();
+        style.set("gridTemplateColumns", &Value::Str("1fr 2fr".to_string())).unwrap();
+        style.set("display", &Value::Str("flex".to_string())).unwrap();
+
+        assert_eq!(
+            style.to_string(),
+            "display: flex;padding-top: 4px;padding-right: 1.5rem;padding-bottom: 4px;padding-left: 1.5rem;\
+margin-top: 8px;background-color: #fafafa;border: 1px solid rgb(0, 0, 0);font-weight: 600;\
+grid-template-columns: 1fr 2fr;"
+        );
+
+        assert_eq!(CSSProps::validate("width", &Value::Str("10".to_string())), Err(r#"invalid width "10""#.to_string()));
+        assert_eq!(style.set("color", &Value::Str("red; x: y".to_string())), Err(r#"invalid color "red; x: y""#.to_string()));
+        assert_eq!(CSSProps::validate("position", &Value::Str("floating".to_string())), Err(r#"invalid position "floating""#.to_string()));
+    }
+
+    #[test]
+    fn newer_css_values() {
+        let valid = [
+            ("maxWidth", "none"),
+            ("width", "fit-content"),
+            ("height", "min-content"),
+            ("width", "min(100%, 40rem)"),
+            ("fontSize", "clamp(1rem, 2.5vw, 2rem)"),
+            ("maxWidth", "60ch"),
+            ("height", "100dvh"),
+            ("width", "50vmin"),
+            ("color", "oklch(70% 0.1 200)"),
+            ("backgroundColor", "color-mix(in srgb, red 50%, white)")
+        ];
+
+        for (name, value) in valid {
+            assert_eq!(CSSProps::validate(name, &Value::Str(value.to_string())), Ok(()), "{}: {}", name, value);
+        }
+
+        // Values that are not understood still render.
+        let mut style = CSSProps::default();
+        style.set("position", &Value::Str("-webkit-sticky".to_string())).unwrap();
+
+        assert_eq!(style.to_string(), "position: -webkit-sticky;");
+    }
+
+    #[test]
+    fn shorthands_keep_source_order() {
+        let mut first = CSSProps::default();
+        first.set("paddingTop", &Value::Int(4)).unwrap();
+
+        let mut second = CSSProps::default();
+        second.set("padding", &Value::Int(8)).unwrap();
+        second.set("paddingTop", &Value::Int(4)).unwrap();
+
+        assert_eq!(
+            second.to_string(),
+            "padding-top: 4px;padding-right: 8px;padding-bottom: 8px;padding-left: 8px;"
+        );
+
+        // The shared padding-top class comes first in the sheet, it must
+        // not be overridden by the padding of the second element.
+        let mut sheet = StyleSheet::default();
+
+        for style in [&first, &second] {
+            for declaration in style.declarations() {
+                sheet.class(declaration);
+            }
+        }
+
+        assert!(!sheet.to_string().contains("padding: "));
+
+        let mut style = CSSProps::default();
+        style.set("border", &Value::Str("1px solid red".to_string())).unwrap();
+
+        assert_eq!(
+            style.set("borderColor", &Value::Str("blue".to_string())),
+            Err("style border-color overlaps border, use only one of them".to_string())
+        );
+    }
+
+    #[test]
+    fn styles_become_shared_classes() {
+        let mut vm = Vm::new();
+
+        let res = vm.run_code(r#"
+        card = {
+            display: "flex"
+            hover: { color: "red" }
+            md: { display: "none" }
+        }
+
+        return Html {
+            head: Head {
+                title: "styles"
+            },
+            body: [
+                Div { style: card, text: "a" }
+                Div { style: card, text: "b" }
+            ]
+        }"#);
+
+        match res {
+            RunResult::Value(value) => {
+                let html = Html::try_from(value).unwrap().to_string();
+                assert_eq!(html, r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>styles</title><style>.s4609ef89{display: flex;}
+.s4baa6f09:hover{color: red;}
+@media (min-width: 768px){.s8ce5aeec{display: none;}}</style></head>
+<body><div class="s4609ef89 s4baa6f09 s8ce5aeec">a</div>
+<div class="s4609ef89 s4baa6f09 s8ce5aeec">b</div></body>
+</html>"#);
+            },
+            _ => todo!()
+        }
+    }
+
+    #[test]
+    fn head_with_meta_links_and_scripts() {
+        let mut vm = Vm::new();
+
+        let res = vm.run_code(r#"
+        return Html {
+            lang: "en"
+            head: Head {
+                title: "Todo"
+                viewport: "width=device-width, initial-scale=1"
+                og: { site_name: "Todo" }
+                stylesheets: ["/app.css"]
+                scripts: ["/a.js", { src: "/b.js", defer: true, module: true }]
+            },
+            body: []
+        }"#);
+
+        match res {
+            RunResult::Value(value) => {
+                let html = Html::try_from(value).unwrap().to_string();
+                assert_eq!(html, r#"<!DOCTYPE html>
+<html lang="en">
+<head><meta charset="utf-8"><title>Todo</title><meta name="viewport" content="width=device-width, initial-scale=1"><meta property="og:site_name" content="Todo"><link rel="stylesheet" href="/app.css"><script src="/a.js"></script>
+<script src="/b.js" defer type="module"></script></head>
+<body></body>
+</html>"#);
+            },
+            _ => todo!()
+        }
+    }
+
+    #[test]
+    fn conversion_errors_have_a_path() {
+        let code = r#"return Html {
+    body: [
+        H1 { text: "a" }
+        Div {
+            children: [
+                Div {
+                    style: {
+                        display: "flex; color: red"
+                    }
+                }
+            ]
+        }
+    ]
+}"#;
+
+        let value = match Vm::new().run_code(code) {
+            RunResult::Value(value) => value,
+            _ => todo!()
+        };
+
+        let err = Html::try_from(value).unwrap_err();
+
+        assert_eq!(err.path_string(), "body[1].children[0].style.display");
+        assert_eq!(
+            err.locate(code).to_string(),
+            r#"8:25: body[1].children[0].style.display: invalid display "flex; color: red""#
+        );
+    }
+}
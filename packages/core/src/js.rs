@@ -1,9 +1,17 @@
 use std::collections::HashSet;
 
 use flexscript::ASTNode;
+use flexscript::ObjProp;
 use flexscript::Value;
+use serde::Serialize;
 
-#[derive(Clone, Debug, PartialEq)]
+use crate::export::STATIC_PARAMS;
+use crate::html::HtmlElType;
+
+/// Attribute the page script finds elements with handlers by.
+pub const ELEMENT_ID_ATTR: &str = "data-m-id";
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub enum JSNode {
     Assign {
         left: Box<JSNode>,
@@ -42,6 +50,8 @@ pub enum JSNode {
         callee: Box<JSNode>,
         args: Vec<JSNode>
     },
+    Array(Vec<JSNode>),
+    Object(Vec<(String, JSNode)>),
     Many(Vec<JSNode>)
 }
 
@@ -62,7 +72,8 @@ impl ToString for JSNode {
             },
             JSNode::Ident(i) => i.clone(),
             JSNode::Number(n) => n.to_string(),
-            JSNode::String(s) => format!("\"{}\"", s),
+            // Json strings are valid JavaScript string literals.
+            JSNode::String(s) => serde_json::to_string(s).unwrap(),
             JSNode::PropAccess { object, property } => {
                 format!("{}.{}", object.to_string(), property.to_string())
             },
@@ -89,6 +100,20 @@ impl ToString for JSNode {
                 args_str.pop();
                 format!("{}({})", callee.to_string(), args_str)
             },
+            JSNode::Array(items) => {
+                let items_str = items.iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("[{}]", items_str)
+            },
+            JSNode::Object(props) => {
+                let props_str = props.iter()
+                    .map(|(name, value)| format!("{}: {}", serde_json::to_string(name).unwrap(), value.to_string()))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("{{{}}}", props_str)
+            },
             JSNode::Many(many) => {
                 let mut many_str = String::new();
                 for node in many {
@@ -104,9 +129,34 @@ impl ToString for JSNode {
     }
 }
 
+/// Where the element being generated is in the page: its path of indexes
+/// in the body, `2_0` for the first child of the third item of the body.
+struct Element {
+    id: String
+}
+
+impl Element {
+    fn new(parent: Option<&str>, inx: usize) -> Self {
+        let id = match parent {
+            Some(parent) => format!("{}_{}", parent, inx),
+            None => inx.to_string()
+        };
+
+        Self {
+            id: id
+        }
+    }
+
+    /// Name the element is bound to, `e_2_0`.
+    fn name(&self) -> String {
+        format!("e_{}", self.id)
+    }
+}
+
 pub struct JSGen {
     log: usize,
     depth: usize,
+    element: Option<Element>,
     pub symbol_table: Vec<HashSet<String>>
 }
 
@@ -115,6 +165,7 @@ impl JSGen {
         Self {
             log: 0,
             depth: 0,
+            element: None,
             symbol_table: Vec::new()
         }
     }
@@ -139,6 +190,27 @@ impl JSGen {
         self.symbol_table.last_mut().unwrap().insert(name.to_string());
     }
 
+    /// Binds the elements in `items` by their index. Every item counts,
+    /// text and values computed at runtime too, the same as in
+    /// `tag_handlers`.
+    fn process_children(&mut self, items: &[ASTNode], parent: Option<&str>) -> Vec<JSNode> {
+        let mut nodes = vec![];
+
+        for (inx, item) in items.iter().enumerate() {
+            if matches!(item, ASTNode::Lit(_)) {
+                continue;
+            }
+
+            if matches!(item, ASTNode::ObjIns(_)) {
+                self.element = Some(Element::new(parent, inx));
+            }
+
+            nodes.push(self.process_node(item));
+        }
+
+        nodes
+    }
+
     pub fn process_node(&mut self, node: &ASTNode) -> JSNode {
         self.depth += 1;
 
@@ -190,6 +262,7 @@ impl JSGen {
                     Value::Int(i) => JSNode::Number(*i as f64),
                     Value::Float(f) => JSNode::Number(*f),
                     Value::Str(s) => JSNode::String(s.clone()),
+                    Value::Bool(b) => JSNode::Ident(b.to_string()),
                     _ => todo!()
                 }
             },
@@ -262,21 +335,9 @@ impl JSGen {
                                             }
                                         );
 
-                                        body.push(
-                                            JSNode::Let { 
-                                                name: "children".to_string(), 
-                                                value: Box::new(JSNode::PropAccess { 
-                                                    object: Box::new(JSNode::Ident("body".to_string())), 
-                                                    property: Box::new(JSNode::Ident("children".to_string()))
-                                                })
-                                            }
-                                        );
-
                                         match &*prop.value {
                                             ASTNode::Array(a) => {
-                                                for item in &a.items {
-                                                    body.push(self.process_node(&item)); 
-                                                }
+                                                body.extend(self.process_children(&a.items, None));
                                             },
                                             _ => todo!()
                                         }
@@ -302,20 +363,26 @@ impl JSGen {
                     JSNode::Ret(None)
                 }
             },
+            ASTNode::Array(a) => {
+                JSNode::Array(a.items.iter().map(|item| self.process_node(item)).collect())
+            },
+            // Objects without a name are plain data.
+            ASTNode::ObjIns(o) if o.name.is_none() => {
+                JSNode::Object(
+                    o.props.iter()
+                        .map(|p| (p.name.clone(), self.process_node(&p.value)))
+                        .collect()
+                )
+            },
             ASTNode::ObjIns(o) => {
                 let mut many = vec![];
 
-                match &o.name {
-                    Some(n) => {
-                        many.push(
-                            JSNode::Let { 
-                                name: "e".to_string(), 
-                                value: Box::new(JSNode::IndexAccess { 
-                                    object:  Box::new(JSNode::Ident("children".to_string())),
-                                    index: Box::new(JSNode::Number(0.0))
-                                })
-                            }
-                        );
+                let element = match &o.name {
+                    Some(_) => {
+                        // An element outside the returned body, like a
+                        // snippet in `monolith repl`, is taken to be the
+                        // first item of the body.
+                        let element = self.element.take().unwrap_or(Element::new(None, 0));
 
                         // many.push(
                         //     JSNode::Let { 
@@ -343,32 +410,70 @@ impl JSGen {
                         //     }),
                         //     args: vec![JSNode::String(n.to_string())]
                         // }
+
+                        element
                     },
                     None => todo!(),
+                };
+
+                let name = element.name();
+
+                // Only elements with handlers are tagged by the server.
+                let has_handlers = o.props.iter().any(|p| p.name.starts_with("on_"));
+
+                if has_handlers {
+                    many.push(
+                        JSNode::Let { 
+                            name: name.clone(), 
+                            value: Box::new(JSNode::Call { 
+                                callee: Box::new(JSNode::PropAccess { 
+                                    object: Box::new(JSNode::Ident("document".to_string())), 
+                                    property: Box::new(JSNode::Ident("querySelector".to_string()))
+                                }), 
+                                args: vec![JSNode::String(format!("[{}=\"{}\"]", ELEMENT_ID_ATTR, element.id))] 
+                            })
+                        }
+                    );
                 }
 
+                let typ = o.name.as_deref().and_then(HtmlElType::from_name);
+
+                // The text of a textarea is its value.
+                let text_property = match typ {
+                    Some(HtmlElType::Textarea) => "value",
+                    _ => "innerText"
+                };
+
                 for prop in &o.props {
                     match prop.name.as_str() {
-                        "text" => {
+                        "text" if has_handlers => {
                             many.push(
                                 JSNode::Assign { 
                                     left: Box::new(JSNode::PropAccess { 
-                                        object: Box::new(JSNode::Ident("e".to_string())), 
-                                        property: Box::new(JSNode::Ident("innerText".to_string()))
+                                        object: Box::new(JSNode::Ident(name.clone())), 
+                                        property: Box::new(JSNode::Ident(text_property.to_string()))
                                     }), 
-                                    right: match &*prop.value {
-                                        ASTNode::Lit(Value::Str(s)) => Box::new(JSNode::String(s.clone())),
-                                        _ => todo!()
-                                    }
+                                    right: Box::new(self.process_node(&prop.value))
                                 }
                             );
                         },
-                        "on_click" => {
+                        "children" => {
+                            // Children computed at runtime are not bound.
+                            if let ASTNode::Array(a) = &*prop.value {
+                                many.extend(self.process_children(&a.items, Some(&element.id)));
+                            }
+                        },
+                        handler if handler.starts_with("on_") => {
+                            let property = match event_property(handler) {
+                                Some(property) => property,
+                                None => panic!("unknown event handler {}", handler)
+                            };
+
                             many.push(
                                 JSNode::Assign { 
                                     left: Box::new(JSNode::PropAccess { 
-                                        object: Box::new(JSNode::Ident("e".to_string())), 
-                                        property: Box::new(JSNode::Ident("onclick".to_string()))
+                                        object: Box::new(JSNode::Ident(name.clone())), 
+                                        property: Box::new(JSNode::Ident(property.to_string()))
                                     }), 
                                     right: Box::new(self.process_node(&prop.value))
                                 }
@@ -422,6 +527,11 @@ impl JSGen {
         let mut js_nodes = Vec::new();
 
         for node in ast {
+            // Only read by `monolith build`, the browser has no use for it.
+            if is_static_params(&node) {
+                continue;
+            }
+
             js_nodes.push(self.process_node(&node));
         }
 
@@ -429,9 +539,106 @@ impl JSGen {
     }
 }
 
+/// Element property an `on_` handler is assigned to.
+fn event_property(name: &str) -> Option<&'static str> {
+    let property = match name {
+        "on_click" => "onclick",
+        "on_double_click" => "ondblclick",
+        "on_context_menu" => "oncontextmenu",
+        "on_mouse_down" => "onmousedown",
+        "on_mouse_up" => "onmouseup",
+        "on_mouse_enter" => "onmouseenter",
+        "on_mouse_leave" => "onmouseleave",
+        "on_mouse_move" => "onmousemove",
+        "on_mouse_over" => "onmouseover",
+        "on_mouse_out" => "onmouseout",
+        "on_pointer_down" => "onpointerdown",
+        "on_pointer_up" => "onpointerup",
+        "on_pointer_move" => "onpointermove",
+        "on_touch_start" => "ontouchstart",
+        "on_touch_end" => "ontouchend",
+        "on_touch_move" => "ontouchmove",
+        "on_wheel" => "onwheel",
+        "on_scroll" => "onscroll",
+        "on_key_down" => "onkeydown",
+        "on_key_up" => "onkeyup",
+        "on_focus" => "onfocus",
+        "on_blur" => "onblur",
+        "on_input" => "oninput",
+        "on_change" => "onchange",
+        "on_submit" => "onsubmit",
+        "on_reset" => "onreset",
+        "on_invalid" => "oninvalid",
+        "on_load" => "onload",
+        "on_error" => "onerror",
+        _ => return None
+    };
+
+    Some(property)
+}
+
+/// Tags the elements with `on_` handlers in the body of a returned `Html`
+/// value with the id `JSGen` binds them by. Run it before the page is put
+/// into a layout, the ids are paths in the page body.
+pub fn tag_handlers(page: &mut Value) {
+    let body = match page {
+        Value::Obj(obj) => obj.props.iter_mut().find(|p| p.name == "body"),
+        _ => None
+    };
+
+    if let Some(ObjProp { value: Value::List(items), .. }) = body {
+        tag_items(items, None);
+    }
+}
+
+/// Tags a single element as the first item of the body, see `JSGen` for
+/// elements outside the returned body.
+pub fn tag_element(value: &mut Value) {
+    tag(value, Element::new(None, 0));
+}
+
+/// Items computed at runtime, like `None` or a list, keep their index, so
+/// they do not shift the ids of the items after them.
+fn tag_items(items: &mut [Value], parent: Option<&str>) {
+    for (inx, item) in items.iter_mut().enumerate() {
+        tag(item, Element::new(parent, inx));
+    }
+}
+
+fn tag(value: &mut Value, element: Element) {
+    let obj = match value {
+        Value::Obj(obj) if obj.name.is_some() => obj,
+        _ => return
+    };
+
+    for p in obj.props.iter_mut().filter(|p| p.name == "children") {
+        if let Value::List(items) = &mut p.value {
+            tag_items(items, Some(&element.id));
+        }
+    }
+
+    if obj.props.iter().any(|p| p.name.starts_with("on_")) {
+        obj.props.push(ObjProp {
+            name: ELEMENT_ID_ATTR.to_string(),
+            value: Value::Str(element.id)
+        });
+    }
+}
+
+pub(crate) fn is_static_params(node: &ASTNode) -> bool {
+    match node {
+        ASTNode::Assign(a) => matches!(&*a.left, ASTNode::Ident(name) if name == STATIC_PARAMS),
+        _ => false
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use flexscript::Parser;
+    use flexscript::RunResult;
+    use flexscript::Vm;
+
+    use crate::html::Html;
 
     use super::*;
 
@@ -508,6 +715,120 @@ mod tests {
         println!("{}", js_code.to_string());
     }
 
+    #[test]
+    fn element_events_and_textarea_text() {
+        let code = r#"
+        return Html {
+            head: Head {
+                title: "form"
+            }
+            body: [
+                Textarea {
+                    text: "hello"
+                    on_input: () => {
+                        print("typed")
+                    }
+                }
+            ]
+        }
+        "#;
+
+        let ast = Parser::new(code).parse();
+        let js_code = JSGen::new().gen(ast).to_string();
+
+        assert!(js_code.contains(r#"e_0.value = "hello""#));
+        assert!(js_code.contains("e_0.oninput = () => {"));
+    }
+
+    #[test]
+    fn binds_handlers_to_their_element() {
+        let code = r#"
+        return Html {
+            head: Head {
+                title: "list"
+            }
+            body: [
+                "intro"
+                H1 {
+                    text: 1.5
+                }
+                Ul {
+                    children: [
+                        Li { text: "a" }
+                        Li {
+                            text: "b"
+                            on_double_click: () => {
+                                print("b")
+                            }
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+
+        let ast = Parser::new(code).parse();
+        let js_code = JSGen::new().gen(ast).to_string();
+
+        assert!(js_code.contains(r#"const e_2_1 = document.querySelector("[data-m-id=\"2_1\"]")"#));
+        assert!(js_code.contains(r#"e_2_1.innerText = "b""#));
+        assert!(js_code.contains("e_2_1.ondblclick = () => {"));
+        // Elements without handlers are left alone.
+        assert!(!js_code.contains("e_1"));
+    }
+
+    #[test]
+    fn computed_children_keep_their_index() {
+        let code = r#"
+        items = ["a", "b"]
+
+        return Html {
+            head: Head {
+                title: "list"
+            }
+            body: [
+                items.map((item) => {
+                    return Li { text: item }
+                })
+                Button {
+                    text: "more"
+                    on_click: () => {
+                        print("more")
+                    }
+                }
+            ]
+        }
+        "#;
+
+        let js_code = JSGen::new().gen(Parser::new(code).parse()).to_string();
+
+        assert!(js_code.contains(r#"const e_1 = document.querySelector("[data-m-id=\"1\"]")"#));
+        assert!(js_code.contains("e_1.onclick = () => {"));
+
+        let mut page = match Vm::new().run_code(code) {
+            RunResult::Value(value) => value,
+            res => panic!("{:?}", res)
+        };
+
+        tag_handlers(&mut page);
+
+        let html = Html::try_from(page).unwrap().to_string();
+
+        assert!(html.contains(r#"<li>b</li>"#));
+        assert!(html.contains(r#"<button data-m-id="1">more</button>"#));
+    }
+
+    #[test]
+    fn todo_example() {
+        let code = include_str!("../../../examples/todo.fs");
+
+        let js_code = JSGen::new().gen(Parser::new(code).parse()).to_string();
+
+        assert!(js_code.contains("const todos = []"));
+        assert!(js_code.contains(r#"todos.push({"name": "Hello", "completed": false})"#));
+        assert!(js_code.contains("e_1_1.onclick = () => {"));
+    }
+
 //     #[test]
 //     fn button_onclick() {
 //         let code = r#"
@@ -0,0 +1,254 @@
+use flexscript::Obj;
+use flexscript::ObjProp;
+use flexscript::Value;
+
+/// Wraps a page in a layout. Both are `Html` values; the `Outlet {}`
+/// element in the layout body is replaced with the page body, or the page
+/// body is appended when the layout has no outlet. Head properties of the
+/// page override the ones of the layout, except lists like `stylesheets`
+/// which are joined.
+pub fn apply_layout(layout: Value, page: Value) -> Value {
+    let (layout, page) = match (layout, page) {
+        (Value::Obj(layout), Value::Obj(page)) => (layout, page),
+        (_, page) => return page
+    };
+
+    let page_head = prop(&page, "head");
+    let page_body = match prop(&page, "body") {
+        Some(Value::List(list)) => list,
+        Some(Value::None) | None => vec![],
+        Some(v) => vec![v]
+    };
+
+    let mut props = layout.props;
+
+    match props.iter_mut().find(|p| p.name == "body") {
+        Some(body) if is_outlet(&body.value) => {
+            body.value = Value::List(page_body);
+        },
+        Some(body) => {
+            let mut page_body = Some(page_body);
+
+            if !replace_outlet(&mut body.value, &mut page_body) {
+                let rest = page_body.unwrap_or_default();
+
+                body.value = match std::mem::replace(&mut body.value, Value::None) {
+                    Value::List(mut list) => {
+                        list.extend(rest);
+                        Value::List(list)
+                    },
+                    Value::None => Value::List(rest),
+                    v => Value::List(std::iter::once(v).chain(rest).collect())
+                };
+            }
+        },
+        None => props.push(ObjProp {
+            name: "body".to_string(),
+            value: Value::List(page_body)
+        })
+    }
+
+    // Like `lang`, the page wins.
+    for p in page.props.into_iter().filter(|p| p.name != "head" && p.name != "body") {
+        match props.iter_mut().find(|l| l.name == p.name) {
+            Some(l) => l.value = p.value,
+            None => props.push(p)
+        }
+    }
+
+    if let Some(page_head) = page_head {
+        match props.iter_mut().find(|p| p.name == "head") {
+            Some(head) => head.value = merge_head(head.value.clone(), page_head),
+            None => props.push(ObjProp {
+                name: "head".to_string(),
+                value: page_head
+            })
+        }
+    }
+
+    Value::Obj(Obj {
+        name: layout.name,
+        props: props
+    })
+}
+
+fn prop(obj: &Obj, name: &str) -> Option<Value> {
+    obj.props.iter()
+        .find(|p| p.name == name)
+        .map(|p| p.value.clone())
+}
+
+fn is_outlet(value: &Value) -> bool {
+    matches!(value, Value::Obj(obj) if obj.name.as_deref() == Some("Outlet"))
+}
+
+/// Replaces the first outlet found in `value`. Returns whether one was
+/// found.
+fn replace_outlet(value: &mut Value, page_body: &mut Option<Vec<Value>>) -> bool {
+    match value {
+        Value::List(list) => {
+            if let Some(inx) = list.iter().position(is_outlet) {
+                let items = page_body.take().unwrap_or_default();
+                list.splice(inx..inx + 1, items);
+
+                return true;
+            }
+
+            list.iter_mut().any(|item| replace_outlet(item, page_body))
+        },
+        Value::Obj(obj) => {
+            for p in obj.props.iter_mut().filter(|p| p.name == "children") {
+                if is_outlet(&p.value) {
+                    p.value = Value::List(page_body.take().unwrap_or_default());
+
+                    return true;
+                }
+
+                if replace_outlet(&mut p.value, page_body) {
+                    return true;
+                }
+            }
+
+            false
+        },
+        _ => false
+    }
+}
+
+fn merge_head(layout: Value, page: Value) -> Value {
+    match (layout, page) {
+        (Value::Obj(mut layout), Value::Obj(page)) => {
+            for p in page.props {
+                match layout.props.iter_mut().find(|l| l.name == p.name) {
+                    // Stylesheets, scripts and the like of both are kept.
+                    Some(l) => match (&mut l.value, p.value) {
+                        (Value::List(list), Value::List(page_list)) => list.extend(page_list),
+                        (value, page_value) => *value = page_value
+                    },
+                    None => layout.props.push(p)
+                }
+            }
+
+            Value::Obj(layout)
+        },
+        (_, page) => page
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Monolith;
+    use crate::value::obj;
+
+    use super::*;
+
+    fn text(s: &str) -> Value {
+        Value::Str(s.to_string())
+    }
+
+    #[test]
+    fn page_body_replaces_outlet() {
+        let layout = obj(Some("Html"), vec![
+            ("head", obj(Some("Head"), vec![("title", text("App"))])),
+            ("body", Value::List(vec![
+                obj(Some("H1"), vec![("text", text("Header"))]),
+                obj(Some("Div"), vec![
+                    ("children", Value::List(vec![obj(Some("Outlet"), vec![])]))
+                ])
+            ]))
+        ]);
+
+        let page = obj(Some("Html"), vec![
+            ("head", obj(Some("Head"), vec![("title", text("Todo"))])),
+            ("body", Value::List(vec![text("a"), text("b")]))
+        ]);
+
+        let res = apply_layout(layout, page);
+
+        let expected = obj(Some("Html"), vec![
+            ("head", obj(Some("Head"), vec![("title", text("Todo"))])),
+            ("body", Value::List(vec![
+                obj(Some("H1"), vec![("text", text("Header"))]),
+                obj(Some("Div"), vec![
+                    ("children", Value::List(vec![text("a"), text("b")]))
+                ])
+            ]))
+        ]);
+
+        assert_eq!(format!("{:?}", res), format!("{:?}", expected));
+    }
+
+    #[test]
+    fn page_body_is_appended_without_outlet() {
+        let layout = obj(Some("Html"), vec![
+            ("body", Value::List(vec![text("header")]))
+        ]);
+        let page = obj(Some("Html"), vec![
+            ("body", Value::List(vec![text("page")]))
+        ]);
+
+        let expected = obj(Some("Html"), vec![
+            ("body", Value::List(vec![text("header"), text("page")]))
+        ]);
+
+        assert_eq!(format!("{:?}", apply_layout(layout, page)), format!("{:?}", expected));
+    }
+
+    #[tokio::test]
+    async fn page_handlers_are_bound_inside_layout() {
+        let layout = r#"
+        return Html {
+            head: Head {
+                title: "App"
+            }
+            body: [
+                H1 {
+                    text: "App"
+                }
+                Div {
+                    children: [
+                        Outlet {}
+                    ]
+                }
+            ]
+        }
+        "#;
+
+        let page = r#"
+        return Html {
+            head: Head {
+                title: "Todo"
+            }
+            body: [
+                H1 {
+                    text: "Todo"
+                }
+                Button {
+                    text: "Add"
+                    on_click: () => {
+                        print("add")
+                    }
+                }
+            ]
+        }
+        "#;
+
+        let res = Monolith::new()
+            .workers(1)
+            .layout("/", layout)
+            .add("/", page)
+            .render("/")
+            .await
+            .unwrap();
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let html = String::from_utf8_lossy(&body);
+
+        // The layout elements are not tagged, the page elements keep the
+        // ids of their place in the page body.
+        assert!(html.contains("<h1>App</h1>"));
+        assert!(html.contains(r#"<button data-m-id="1">Add</button>"#));
+        assert!(html.contains(r#"const e_1 = document.querySelector("[data-m-id=\"1\"]")"#));
+        assert!(html.contains("e_1.onclick = () => {"));
+    }
+}
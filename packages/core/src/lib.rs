@@ -5,7 +5,33 @@ mod handle_request;
 mod test_util;
 mod html;
 mod js;
+mod value;
+mod vm_pool;
+mod resolvers;
+mod errors;
+mod request;
+mod response;
+mod form;
+mod static_files;
+mod server;
+mod shutdown;
+mod reload;
+mod layout;
+mod export;
+mod check;
+mod emit;
+mod repl;
+mod store;
 
 pub use monolith::*;
 pub use gui::*;
-pub use match_routes::*;
\ No newline at end of file
+pub use match_routes::*;
+pub use hyper::Method;
+pub use static_files::EmbeddedAsset;
+pub use html::HtmlError;
+pub use html::PathSegment;
+pub use server::*;
+pub use shutdown::*;
+pub use check::*;
+pub use emit::*;
+pub use repl::*;
\ No newline at end of file
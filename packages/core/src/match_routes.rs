@@ -1,5 +1,9 @@
 use std::collections::HashMap;
 
+use percent_encoding::percent_decode_str;
+
+/// Matches `route` against `pattern`, like `/todo/:id`. Returns the
+/// percent-decoded value of every `:name` segment.
 pub fn does_route_match(pattern: &str, route: &str) -> Option<HashMap<String, String>> {
     let mut pattern_chars = pattern.chars().into_iter();
     let mut route_chars = route.chars().into_iter();
@@ -51,7 +55,13 @@ pub fn does_route_match(pattern: &str, route: &str) -> Option<HashMap<String, St
         }
 
         if param_name.len() > 0 {
-            params.insert(param_name, param_value);
+            // `/todo/` has no id.
+            if param_value.is_empty() {
+                return None;
+            }
+
+            let value = percent_decode_str(&param_value).decode_utf8_lossy().to_string();
+            params.insert(param_name, value);
             param_name = String::new();
             param_value = String::new();
 
@@ -89,6 +99,29 @@ pub fn find_matches(patterns: &Vec<String>, route: &str) -> Option<(String, Hash
     None
 }
 
+/// Whether `path` is `prefix` or lies under it. Compares whole segments, so
+/// `/todo` is a prefix of `/todo/1` but not of `/todos`. A `:name` segment
+/// in the prefix matches any segment.
+pub fn is_path_prefix(prefix: &str, path: &str) -> bool {
+    let mut path_segments = path.split('/').filter(|s| !s.is_empty());
+
+    prefix.split('/')
+        .filter(|s| !s.is_empty())
+        .all(|segment| match path_segments.next() {
+            Some(p) => segment.starts_with(':') || segment == p,
+            None => false
+        })
+}
+
+pub fn parse_query(query: Option<&str>) -> HashMap<String, String> {
+    match query {
+        Some(query) => form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect(),
+        None => HashMap::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,6 +165,20 @@ mod tests {
         assert_eq!(map, None);
     }
 
+    #[test]
+    fn test_does_match_decodes_params() {
+        let map = does_route_match("/todo/:id", "/todo/a%20b");
+
+        assert_eq!(map, Some([("id".to_string(), "a b".to_string())].iter().cloned().collect()));
+    }
+
+    #[test]
+    fn test_does_not_match_empty_param() {
+        assert_eq!(does_route_match("/todo/:id", "/todo/"), None);
+        assert_eq!(does_route_match("/todo/:id", "/todo"), None);
+        assert_eq!(does_route_match("/todo/:id/edit", "/todo//edit"), None);
+    }
+
     #[test]
     fn test_find_matches() {
         let routes = vec![
@@ -149,4 +196,29 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_is_path_prefix() {
+        assert!(is_path_prefix("/", "/"));
+        assert!(is_path_prefix("/", "/todo/1"));
+        assert!(is_path_prefix("/todo", "/todo"));
+        assert!(is_path_prefix("/todo/", "/todo/1"));
+        assert!(!is_path_prefix("/todo", "/todos"));
+        assert!(is_path_prefix("/todo/:id", "/todo/1/edit"));
+        assert!(!is_path_prefix("/todo/:id", "/todo"));
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = parse_query(Some("name=makkara&filter=a%20b"));
+
+        assert_eq!(
+            query,
+            vec![
+                ("name".to_string(), "makkara".to_string()),
+                ("filter".to_string(), "a b".to_string())
+            ].into_iter().collect()
+        );
+        assert_eq!(parse_query(None), HashMap::new());
+    }
 }
\ No newline at end of file
@@ -1,132 +1,656 @@
+use std::collections::HashMap;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::panic::catch_unwind;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 use flexscript::Parser;
 use flexscript::RunResult;
 use flexscript::Value;
-use flexscript::Vm;
 use hyper::Body;
+use hyper::Method;
+use hyper::Request;
 use hyper::Response;
-use hyper::server::conn;
-use hyper::service::service_fn;
-use tokio::net::TcpListener;
+use hyper::StatusCode;
+use hyper::header;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
 
+use crate::Client;
+use crate::errors::PageError;
+use crate::errors::panic_message;
+use crate::export;
+use crate::handle_request::UI_PATH;
+use crate::handle_request::upgrade_ui;
 use crate::html::Html;
 use crate::html::Script;
+use crate::js;
 use crate::js::JSNode;
 use crate::js::JSGen;
+use crate::layout::apply_layout;
+use crate::match_routes::does_route_match;
+use crate::match_routes::is_path_prefix;
+use crate::reload;
+use crate::reload::RELOAD_PATH;
+use crate::reload::ReloadEvent;
+use crate::request::PageRequest;
+use crate::resolvers::Resolvers;
+use crate::response::PageResponse;
+use crate::response::ResponseBody;
+use crate::server::Server;
+use crate::shutdown::ShutdownSignal;
+use crate::static_files::EmbeddedAsset;
+use crate::static_files::StaticMount;
+use crate::value;
+use crate::vm_pool::VmPool;
 
 #[derive(Clone)]
 struct Route {
+    method: Method,
     path: String,
-    blk: u32,
-    js_node: JSNode
+    code: String,
+    js_node: Result<JSNode, String>
 }
 
+impl Route {
+    fn new(method: Method, path: &str, code: &str) -> Self {
+        let js = catch_unwind(|| {
+            let ast = Parser::new(code).parse();
+            JSGen::new().gen(ast)
+        }).map_err(panic_message);
+
+        if let Err(err) = &js {
+            log::error!("failed to generate js for {}: {}", path, err);
+        }
+
+        Self {
+            method: method,
+            path: path.to_string(),
+            code: code.to_string(),
+            js_node: js
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Layout {
+    prefix: String,
+    code: String
+}
 
 pub struct Monolith {
+    host: String,
     port: u16,
+    #[cfg(unix)]
+    unix_socket: Option<PathBuf>,
+    dev: bool,
+    workers: usize,
+    shutdown_timeout: Duration,
     routes: Vec<Route>,
-    vm: Vm
+    layouts: Vec<Layout>,
+    not_found: Option<Route>,
+    statics: Vec<StaticMount>,
+    resolvers: Resolvers,
+    settings: HashMap<String, String>,
+    ui_clients: Option<mpsc::UnboundedSender<Client>>
 }
 
 impl Monolith
 {
     pub fn new() -> Self {
         Self {
-            port: 80,
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            #[cfg(unix)]
+            unix_socket: None,
+            dev: false,
+            workers: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            shutdown_timeout: Duration::from_secs(10),
             routes: Vec::new(),
-            vm: Vm::new()
+            layouts: Vec::new(),
+            not_found: None,
+            statics: Vec::new(),
+            resolvers: Resolvers::new(),
+            settings: HashMap::new(),
+            ui_clients: None
         }
     }
 
-    pub fn add(mut self, path: &str, code: &str) -> Self {
-        let blk = self.vm.compile_code(code);
-        let ast = Parser::new(code).parse();
-        let js = JSGen::new().gen(ast);
+    /// Adds a page served for GET (and HEAD) requests to `path`.
+    pub fn add(self, path: &str, code: &str) -> Self {
+        self.route(Method::GET, path, code)
+    }
 
-        self.routes.push(Route {
-            path: path.to_string(),
-            blk: blk,
-            js_node: js
+    /// Adds a handler for `method` requests to `path`. Several handlers can
+    /// share a path as long as their methods differ.
+    pub fn route(mut self, method: Method, path: &str, code: &str) -> Self {
+        self.routes.push(Route::new(method, path, code));
+
+        self
+    }
+
+    /// Wraps the `Html` of every page under `prefix` in the `Html` `code`
+    /// returns. The page body takes the place of the `Outlet {}` element and
+    /// page head properties override the layout ones. Nested layouts are
+    /// applied innermost first. Layouts are rendered on the server only.
+    pub fn layout(mut self, prefix: &str, code: &str) -> Self {
+        self.layouts.push(Layout {
+            prefix: prefix.to_string(),
+            code: code.to_string()
         });
 
         self
     }
 
+    /// Page rendered with 404 Not Found when no route matches.
+    pub fn not_found(mut self, code: &str) -> Self {
+        self.not_found = Some(Route::new(Method::GET, "404", code));
+
+        self
+    }
+
+    /// Adds a handler for forms posted to `path`. The handler gets the
+    /// submitted fields as `form`, a field sent several times as a list,
+    /// and can return `Html` to re-render the page or
+    /// `Redirect { to: "..." }` to redirect with 303 See Other.
+    pub fn on_submit(self, path: &str, code: &str) -> Self {
+        self.route(Method::POST, path, code)
+    }
+
+    /// Serves files from directory `path` under `url_prefix`. Static files
+    /// are looked up before routes.
+    pub fn static_dir(mut self, url_prefix: &str, path: impl Into<PathBuf>) -> Self {
+        self.statics.push(StaticMount::dir(url_prefix, path));
+
+        self
+    }
+
+    /// Serves files compiled into the binary under `url_prefix`.
+    pub fn embedded_assets(mut self, url_prefix: &str, assets: &'static [EmbeddedAsset]) -> Self {
+        self.statics.push(StaticMount::embedded(url_prefix, assets));
+
+        self
+    }
+
+    /// Port to listen on, 8080 by default. Port 0 picks a free one.
     pub fn listen(mut self, port: u16) -> Self {
         self.port = port;
-        
+
         self
     }
 
-    pub async fn start(mut self) {
-        log::info!("listening {}", self.port);
-        let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
-        let listener = TcpListener::bind(addr).await.unwrap();
-
-        loop {
-            let (stream, _) = listener.accept().await.unwrap();
-
-            log::info!("new client connected");
-
-            match conn::Http::new().serve_connection(stream, service_fn(
-                |req| {
-                    let uri = req.uri();
-
-                    let route = {
-                        let mut route = None;
-
-                        for r in &self.routes {
-                            if uri.path() == r.path {
-                                route = Some(r.clone());
-                                break;
-                            }
-                        }
-
-                        route
-                    };
-
-                    let res = match route {
-                        Some(route) => {
-                            let res = self.vm.run_blk(route.blk, Value::None);
-
-                            match res {
-                                RunResult::Value(value) => {
-                                    let mut html = Html::from(value);
-                                    html.head.scripts.push(Script {
-                                        content: Some(route.js_node.clone())
-                                    });
-                                    let str = html.to_string();
-                                    log::info!("html: {}", str);
-
-                                    Response::new(Body::from(str))
-                                },
-                                RunResult::Await { stack_id, value } => {
-                                    Response::new(Body::from("Not found"))
-                                },
-                                RunResult::None => {
-                                    Response::new(Body::from("Not found"))
-                                },
-                            }
-                        },
-                        None => {
-                            Response::new(Body::from("Not found"))
-                        }
-                    };
-
-                    async move { 
-                        wrap_res(res).await
-                    }
-                }
-            )).await {
-                Ok(_) => {},
-                Err(err) => {
-                    println!("error: {}", err);
+    /// Host name or ip address to listen on, for example `0.0.0.0` or
+    /// `::` for all interfaces.
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+
+        self
+    }
+
+    pub fn bind_addr(mut self, addr: SocketAddr) -> Self {
+        self.host = addr.ip().to_string();
+        self.port = addr.port();
+
+        self
+    }
+
+    /// Listens on a unix domain socket instead of tcp.
+    #[cfg(unix)]
+    pub fn unix_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.unix_socket = Some(path.into());
+
+        self
+    }
+
+    /// Development mode shows the failing route and error message on
+    /// error pages and live reloads open pages when routes are reloaded.
+    pub fn dev(mut self, dev: bool) -> Self {
+        self.dev = dev;
+
+        self
+    }
+
+    /// Number of Vm instances serving requests in parallel.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+
+        self
+    }
+
+    /// How long shutdown waits for in-flight requests and websocket
+    /// clients before dropping them.
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+
+        self
+    }
+
+    /// Registers a resolver for objects named `name` that pages await, for
+    /// example `await Query { sql: "..." }`. The page is resumed with the
+    /// value the resolver returns.
+    pub fn resolver<F, Fut>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Value> + Send + 'static
+    {
+        self.resolvers.add(name, f);
+
+        self
+    }
+
+    /// Value pages can read as `settings.<name>`, for example a database
+    /// url from the configuration.
+    pub fn setting(mut self, name: &str, value: &str) -> Self {
+        self.settings.insert(name.to_string(), value.to_string());
+
+        self
+    }
+
+    /// Accepts websocket connections on `/ui` and sends each as a `Client`
+    /// to `clients`. Clients are sent a close frame when the server shuts
+    /// down.
+    pub fn ui_clients(mut self, clients: mpsc::UnboundedSender<Client>) -> Self {
+        self.ui_clients = Some(clients);
+
+        self
+    }
+
+    /// Binds the listening socket without serving yet. Use
+    /// `Server::local_addr` to find the port picked for port 0.
+    pub async fn bind(self) -> anyhow::Result<Server> {
+        #[cfg(unix)]
+        if let Some(path) = self.unix_socket.clone() {
+            return Server::bind_unix(path, App::new(self)).await;
+        }
+
+        let host = self.host.clone();
+        let port = self.port;
+
+        Server::bind_tcp(&host, port, App::new(self)).await
+    }
+
+    /// Renders `path` like a GET request to a running server would.
+    pub async fn render(self, path: &str) -> anyhow::Result<Response<Body>> {
+        let req = Request::get(path).body(Body::empty())?;
+
+        match App::new(self).render(req).await {
+            Ok(res) => Ok(res),
+            Err(err) => anyhow::bail!("{}", err)
+        }
+    }
+
+    /// Renders every GET route into `out_dir` for static hosting, see
+    /// `monolith build`. Routes with params are rendered once for every
+    /// entry of the `static_params` list in their code. Static files are
+    /// copied along. Returns the written pages.
+    pub async fn export(self, out_dir: impl AsRef<Path>) -> anyhow::Result<Vec<PathBuf>> {
+        export::export(App::new(self), out_dir.as_ref()).await
+    }
+
+    pub async fn start(self) {
+        self.bind().await.unwrap().run().await
+    }
+}
+
+/// Routes together with the Vms they are compiled into. Swapped as a
+/// whole on reload so in-flight requests finish on the old version.
+///
+/// The pool holds the routes first, then the layouts and last the not
+/// found page.
+struct RouteTable {
+    routes: Vec<Route>,
+    layouts: Vec<Layout>,
+    not_found: Option<Route>,
+    pool: VmPool
+}
+
+impl RouteTable {
+    fn new(monolith: &Monolith) -> Self {
+        let codes = monolith.routes.iter()
+            .map(|r| r.code.clone())
+            .chain(monolith.layouts.iter().map(|l| l.code.clone()))
+            .chain(monolith.not_found.iter().map(|r| r.code.clone()))
+            .collect();
+
+        Self {
+            pool: VmPool::new(monolith.workers, codes),
+            routes: monolith.routes.clone(),
+            layouts: monolith.layouts.clone(),
+            not_found: monolith.not_found.clone()
+        }
+    }
+
+    fn not_found_inx(&self) -> usize {
+        self.routes.len() + self.layouts.len()
+    }
+
+    fn route(&self, inx: usize) -> &Route {
+        match self.routes.get(inx) {
+            Some(route) => route,
+            None => self.not_found.as_ref().unwrap()
+        }
+    }
+
+    fn name(&self, inx: usize) -> String {
+        if let Some(r) = self.routes.get(inx) {
+            return format!("{} {}", r.method, r.path);
+        }
+
+        match self.layouts.get(inx - self.routes.len()) {
+            Some(l) => format!("layout {}", l.prefix),
+            None => "404 page".to_string()
+        }
+    }
+
+    /// Layouts for `path`, innermost first.
+    fn layouts_for(&self, path: &str) -> Vec<usize> {
+        let mut layouts = self.layouts.iter()
+            .enumerate()
+            .filter(|(_, l)| is_path_prefix(&l.prefix, path))
+            .collect::<Vec<_>>();
+
+        layouts.sort_by_key(|(_, l)| {
+            std::cmp::Reverse(l.prefix.split('/').filter(|s| !s.is_empty()).count())
+        });
+
+        layouts.into_iter()
+            .map(|(inx, _)| self.routes.len() + inx)
+            .collect()
+    }
+
+    fn errors(&self) -> Vec<String> {
+        let js_errors = self.routes.iter()
+            .chain(self.not_found.iter())
+            .filter_map(|r| {
+                r.js_node.as_ref().err()
+                    .map(|err| format!("{} {}: {}", r.method, r.path, err))
+            });
+
+        let compile_errors = self.pool.compile_errors()
+            .into_iter()
+            .map(|(inx, err)| format!("{}: {}", self.name(inx), err));
+
+        compile_errors.chain(js_errors).collect()
+    }
+}
+
+pub(crate) struct App {
+    table: RwLock<Arc<RouteTable>>,
+    workers: usize,
+    statics: Vec<StaticMount>,
+    resolvers: Resolvers,
+    settings: HashMap<String, String>,
+    dev: bool,
+    reload_tx: broadcast::Sender<ReloadEvent>,
+    ui_clients: Option<mpsc::UnboundedSender<Client>>,
+    next_client_id: AtomicUsize,
+    pub(crate) shutdown_timeout: Duration
+}
+
+impl App {
+    fn new(monolith: Monolith) -> Self {
+        let table = RouteTable::new(&monolith);
+
+        for err in table.errors() {
+            log::error!("{}", err);
+        }
+
+        Self {
+            table: RwLock::new(Arc::new(table)),
+            workers: monolith.workers,
+            statics: monolith.statics,
+            resolvers: monolith.resolvers,
+            settings: monolith.settings,
+            dev: monolith.dev,
+            reload_tx: broadcast::channel(16).0,
+            ui_clients: monolith.ui_clients,
+            next_client_id: AtomicUsize::new(0),
+            shutdown_timeout: monolith.shutdown_timeout
+        }
+    }
+
+    fn reload(&self, monolith: Monolith) -> Vec<String> {
+        let table = RouteTable::new(&monolith.workers(self.workers));
+        let errors = table.errors();
+
+        *self.table.write().unwrap() = Arc::new(table);
+
+        let event = match errors.is_empty() {
+            true => ReloadEvent::Reload,
+            false => ReloadEvent::CompileError(errors.join("\n"))
+        };
+
+        // No receivers just means no browser tab is open.
+        let _ = self.reload_tx.send(event);
+
+        errors
+    }
+
+    pub(crate) async fn handle(&self, req: Request<Body>, signal: ShutdownSignal) -> Response<Body> {
+        if self.dev && req.uri().path() == RELOAD_PATH {
+            return reload::event_stream(self.reload_tx.subscribe(), signal);
+        }
+
+        if let Some(clients) = &self.ui_clients {
+            if req.uri().path() == UI_PATH && hyper_tungstenite::is_upgrade_request(&req) {
+                let id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+
+                return upgrade_ui(req, id, clients, signal);
+            }
+        }
+
+        for mount in &self.statics {
+            if let Some(res) = mount.serve(&req).await {
+                return res;
+            }
+        }
+
+        match self.render(req).await {
+            Ok(res) => res,
+            Err(err) => err.into_response(self.dev)
+        }
+    }
+
+    /// Path and code of every GET route.
+    pub(crate) fn pages(&self) -> Vec<(String, String)> {
+        let table = self.table.read().unwrap().clone();
+
+        table.routes.iter()
+            .filter(|r| r.method == Method::GET)
+            .map(|r| (r.path.clone(), r.code.clone()))
+            .collect()
+    }
+
+    pub(crate) fn has_not_found_page(&self) -> bool {
+        self.table.read().unwrap().not_found.is_some()
+    }
+
+    pub(crate) fn statics(&self) -> &[StaticMount] {
+        &self.statics
+    }
+
+    pub(crate) async fn render(&self, req: Request<Body>) -> Result<Response<Body>, PageError> {
+        let table = self.table.read().unwrap().clone();
+
+        let mut allowed = vec![];
+        let mut found = None;
+
+        for (inx, route) in table.routes.iter().enumerate() {
+            let params = match does_route_match(&route.path, req.uri().path()) {
+                Some(params) => params,
+                None => continue
+            };
+
+            let method_matches = route.method == req.method() ||
+                (route.method == Method::GET && req.method() == Method::HEAD);
+
+            if method_matches {
+                found = Some((inx, params));
+                break;
+            }
+
+            allowed.push(route.method.clone());
+
+            if route.method == Method::GET {
+                allowed.push(Method::HEAD);
+            }
+        }
+
+        let (inx, params, status) = match found {
+            Some((inx, params)) => (inx, params, None),
+            None if !allowed.is_empty() => return Err(PageError::MethodNotAllowed(allowed)),
+            None if table.not_found.is_some() => {
+                (table.not_found_inx(), HashMap::new(), Some(StatusCode::NOT_FOUND))
+            },
+            None => return Err(PageError::NotFound)
+        };
+
+        self.render_route(&table, inx, params, status, req).await
+    }
+
+    /// Renders the not found page with 404 Not Found whatever the path of
+    /// `req` is, see `monolith build`.
+    pub(crate) async fn render_not_found(&self, req: Request<Body>) -> Result<Response<Body>, PageError> {
+        let table = self.table.read().unwrap().clone();
+
+        if table.not_found.is_none() {
+            return Err(PageError::NotFound);
+        }
+
+        let inx = table.not_found_inx();
+
+        self.render_route(&table, inx, HashMap::new(), Some(StatusCode::NOT_FOUND), req).await
+    }
+
+    async fn render_route(
+        &self,
+        table: &RouteTable,
+        inx: usize,
+        params: HashMap<String, String>,
+        status: Option<StatusCode>,
+        req: Request<Body>
+    ) -> Result<Response<Body>, PageError> {
+        let route = table.route(inx);
+        let path = req.uri().path().to_string();
+
+        let request = PageRequest::read(req, params).await?;
+
+        let args = value::obj(None, vec![
+            ("request", request.to_value()),
+            ("params", value::str_map(&request.params)),
+            ("query", value::str_map(&request.query)),
+            ("form", value::fields_map(&request.form)),
+            ("settings", value::str_map(&self.settings))
+        ]);
+
+        let value = self.run(table, inx, args.clone()).await
+            .map_err(|err| PageError::internal(&route.path, err))?;
+
+        let mut page_res = PageResponse::try_from(value)
+            .map_err(|err| PageError::internal(&route.path, err))?;
+
+        if status.is_some() {
+            page_res.status = status;
+        }
+
+        if let ResponseBody::Html(mut page) = page_res.body {
+            // The page script binds elements by their place in the page
+            // body, so they are tagged before a layout wraps the page.
+            js::tag_handlers(&mut page);
+
+            for layout_inx in table.layouts_for(&path) {
+                let name = table.name(layout_inx);
+
+                let layout = self.run(table, layout_inx, args.clone()).await
+                    .map_err(|err| PageError::internal(&name, err))?;
+
+                page = match ResponseBody::from(layout) {
+                    ResponseBody::Html(layout) => apply_layout(layout, page),
+                    _ => return Err(PageError::internal(&name, "layout did not return Html"))
+                };
+            }
+
+            page_res.body = ResponseBody::Html(page);
+        }
+
+        let builder = page_res.builder();
+
+        let res = match page_res.body {
+            ResponseBody::Html(value) => {
+                let js_node = route.js_node.clone()
+                    .map_err(|err| PageError::internal(&route.path, err))?;
+
+                let mut html = Html::try_from(value)
+                    .map_err(|err| PageError::internal(&route.path, err.locate(&route.code)))?;
+
+                html.head.scripts.push(Script::inline(js_node));
+
+                let mut str = html.to_string();
+
+                if self.dev {
+                    str = reload::inject_client(str);
                 }
+
+                log::debug!("html: {}", str);
+
+                builder
+                    .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+                    .body(Body::from(str))
+            },
+            ResponseBody::Json(value) => {
+                builder
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(value::to_json(&value).to_string()))
+            },
+            ResponseBody::Text(text) => {
+                builder
+                    .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+                    .body(Body::from(text))
+            },
+            ResponseBody::Empty => builder.body(Body::empty())
+        };
+
+        res.map_err(|err| PageError::internal(&route.path, err))
+    }
+
+    async fn run(&self, table: &RouteTable, inx: usize, args: Value) -> anyhow::Result<Value> {
+        match table.pool.run(inx, args, &self.resolvers).await? {
+            RunResult::Value(value) => Ok(value),
+            RunResult::Await { .. } | RunResult::None => {
+                anyhow::bail!("page did not return a value")
             }
         }
     }
 }
 
-async fn wrap_res(res: Response<Body>) -> anyhow::Result<Response<Body>> {
-    Ok(res)
-}
\ No newline at end of file
+/// Swaps the routes of a running server, see `Server::reloader`.
+#[derive(Clone)]
+pub struct Reloader {
+    app: Arc<App>
+}
+
+impl Reloader {
+    pub(crate) fn new(app: Arc<App>) -> Self {
+        Self {
+            app: app
+        }
+    }
+
+    /// Replaces the routes of the running server with the routes added to
+    /// `monolith`; its other settings are ignored. Open pages in development
+    /// mode reload, or show the returned compile errors in an overlay.
+    pub async fn reload(&self, monolith: Monolith) -> Vec<String> {
+        let app = self.app.clone();
+
+        tokio::task::spawn_blocking(move || app.reload(monolith))
+            .await
+            .unwrap()
+    }
+}
@@ -0,0 +1,104 @@
+use std::convert::Infallible;
+
+use hyper::Body;
+use hyper::Response;
+use hyper::header;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::ShutdownSignal;
+
+pub const RELOAD_PATH: &str = "/__monolith/reload";
+
+/// Listens for reload events and either reloads the page or shows compile
+/// errors in an overlay on top of it.
+const CLIENT_SCRIPT: &str = r#"(() => {
+    const source = new EventSource("/__monolith/reload");
+    source.addEventListener("reload", () => location.reload());
+    source.addEventListener("compile-error", (e) => {
+        let overlay = document.getElementById("__monolith_overlay");
+        if (!overlay) {
+            overlay = document.createElement("pre");
+            overlay.id = "__monolith_overlay";
+            overlay.style.cssText = "position:fixed;inset:0;margin:0;padding:24px;z-index:2147483647;overflow:auto;background:rgba(24,0,0,0.92);color:#ff8080;font:14px monospace;white-space:pre-wrap";
+            document.body.appendChild(overlay);
+        }
+        overlay.textContent = e.data;
+    });
+})();"#;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReloadEvent {
+    Reload,
+    CompileError(String)
+}
+
+impl ReloadEvent {
+    fn to_sse(&self) -> String {
+        match self {
+            ReloadEvent::Reload => "event: reload\ndata: \n\n".to_string(),
+            ReloadEvent::CompileError(message) => {
+                let data = message.lines()
+                    .map(|line| format!("data: {}\n", line))
+                    .collect::<String>();
+
+                format!("event: compile-error\n{}\n", data)
+            }
+        }
+    }
+}
+
+/// Adds the reload client to a rendered html document.
+pub fn inject_client(html: String) -> String {
+    let script = format!("<script>{}</script>", CLIENT_SCRIPT);
+
+    match html.rfind("</head>") {
+        Some(i) => format!("{}{}{}", &html[..i], script, &html[i..]),
+        None => format!("{}{}", script, html)
+    }
+}
+
+/// Server sent event stream of reload events. The stream ends when the
+/// server shuts down.
+pub fn event_stream(rx: broadcast::Receiver<ReloadEvent>, signal: ShutdownSignal) -> Response<Body> {
+    let stream = futures::stream::unfold((rx, signal), |(mut rx, mut signal)| async move {
+        loop {
+            tokio::select! {
+                _ = signal.wait() => return None,
+                event = rx.recv() => match event {
+                    Ok(event) => return Some((Ok::<_, Infallible>(event.to_sse()), (rx, signal))),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None
+                }
+            }
+        }
+    });
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(Body::wrap_stream(stream))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_events() {
+        assert_eq!(ReloadEvent::Reload.to_sse(), "event: reload\ndata: \n\n");
+        assert_eq!(
+            ReloadEvent::CompileError("line 1\nline 2".to_string()).to_sse(),
+            "event: compile-error\ndata: line 1\ndata: line 2\n\n"
+        );
+    }
+
+    #[test]
+    fn injects_client_into_head() {
+        let html = inject_client("<html><head><title></title></head><body></body></html>".to_string());
+
+        assert!(html.starts_with("<html><head><title></title><script>"));
+        assert!(html.ends_with("</script></head><body></body></html>"));
+    }
+}
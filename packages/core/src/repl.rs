@@ -0,0 +1,163 @@
+use std::panic::AssertUnwindSafe;
+use std::panic::catch_unwind;
+
+use flexscript::ASTNode;
+use flexscript::Parser;
+use flexscript::RunResult;
+use flexscript::Value;
+use flexscript::Vm;
+
+use crate::errors::panic_message;
+use crate::html::Html;
+use crate::html::HtmlEl;
+use crate::html::HtmlError;
+use crate::html::HtmlElType;
+use crate::js;
+use crate::js::JSGen;
+use crate::resolvers::Resolvers;
+use crate::value;
+
+/// Result of evaluating a snippet.
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    /// Returned value, as html for `Html` and elements and as json
+    /// otherwise. `None` when nothing was returned.
+    pub output: Option<String>,
+    /// Script `JSGen` generates for the snippet, or why it can not.
+    pub js: Result<String, String>
+}
+
+/// Evaluates snippets one after another in the same Vm, so variables
+/// carry over between them, see `monolith repl`. Awaited values go through
+/// the same resolvers as pages.
+pub struct Repl {
+    vm: Vm,
+    resolvers: Resolvers
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            vm: Vm::new(),
+            resolvers: Resolvers::new()
+        }
+    }
+
+    /// Forgets every variable.
+    pub fn reset(&mut self) {
+        self.vm = Vm::new();
+    }
+
+    /// Runs `code`. A snippet that is a single expression returns its value
+    /// without writing `return`. Returned `Html` or elements that can not be
+    /// converted are an error.
+    pub async fn eval(&mut self, code: &str) -> anyhow::Result<Evaluation> {
+        let ast = catch_unwind(|| Parser::new(code).parse())
+            .map_err(|err| anyhow::anyhow!("parse error: {}", panic_message(err)))?;
+
+        let is_expression = match ast.as_slice() {
+            [node] => !matches!(node, ASTNode::Assign(_) | ASTNode::Ret(_) | ASTNode::For(_) | ASTNode::If(_)),
+            _ => false
+        };
+
+        let run_code = match is_expression {
+            true => format!("return {}", code.trim()),
+            false => code.to_string()
+        };
+
+        let js = catch_unwind(|| JSGen::new().gen(ast).to_string())
+            .map_err(panic_message);
+
+        let mut res = self.run(|vm| vm.run_code(&run_code))?;
+
+        while let RunResult::Await { stack_id, value } = res {
+            let value = self.resolvers.resolve(value).await;
+            res = self.run(|vm| vm.resume(stack_id, value))?;
+        }
+
+        let output = match res {
+            RunResult::Value(value) => Some(render(value).map_err(|err| err.locate(code))?),
+            _ => None
+        };
+
+        Ok(Evaluation {
+            output: output,
+            js: js
+        })
+    }
+
+    fn run(&mut self, f: impl FnOnce(&mut Vm) -> RunResult) -> anyhow::Result<RunResult> {
+        match catch_unwind(AssertUnwindSafe(|| f(&mut self.vm))) {
+            Ok(res) => Ok(res),
+            Err(err) => {
+                // The Vm state can not be trusted after a panic.
+                self.reset();
+
+                anyhow::bail!("{} (variables were reset)", panic_message(err))
+            }
+        }
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn render(mut value: Value) -> Result<String, HtmlError> {
+    let name = match &value {
+        Value::Obj(obj) => obj.name.clone(),
+        _ => None
+    };
+
+    match name.as_deref() {
+        Some("Html") => {
+            js::tag_handlers(&mut value);
+            Html::try_from(value).map(|html| html.to_string())
+        },
+        Some(name) if HtmlElType::from_name(name).is_some() => {
+            js::tag_element(&mut value);
+            HtmlEl::try_from(value).map(|el| el.to_string())
+        },
+        _ => Ok(serde_json::to_string_pretty(&value::to_json(&value)).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn variables_carry_over() {
+        let mut repl = Repl::new();
+
+        repl.eval("a = 5").await.unwrap();
+        let res = repl.eval("a").await.unwrap();
+
+        assert_eq!(res.output, Some("5".to_string()));
+    }
+
+    #[tokio::test]
+    async fn renders_elements_as_html() {
+        let mut repl = Repl::new();
+
+        let res = repl.eval(r#"H1 { text: "hello" }"#).await.unwrap();
+
+        assert_eq!(res.output, Some("<h1>hello</h1>".to_string()));
+
+        let res = repl.eval(r#"Button { text: "hello" on_click: () => { print("hi") } }"#).await.unwrap();
+
+        assert_eq!(res.output, Some(r#"<button data-m-id="0">hello</button>"#.to_string()));
+        assert!(res.js.unwrap().contains(r#"e_0.innerText = "hello""#));
+    }
+
+    #[tokio::test]
+    async fn shows_html_errors() {
+        let mut repl = Repl::new();
+
+        let err = repl.eval(r#"Div { style: { display: "flex; color: red" } }"#).await.unwrap_err();
+
+        assert_eq!(err.to_string(), r#"1:16: style.display: invalid display "flex; color: red""#);
+    }
+}
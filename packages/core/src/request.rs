@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use flexscript::Value;
+use hyper::Body;
+use hyper::body::HttpBody;
+use hyper::Request;
+use hyper::header;
+use percent_encoding::percent_decode_str;
+
+use crate::errors::PageError;
+use crate::form::Fields;
+use crate::form::FormFile;
+use crate::form::parse_form;
+use crate::match_routes::parse_query;
+use crate::value;
+
+pub const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Incoming request as seen by page code.
+#[derive(Debug, Clone)]
+pub struct PageRequest {
+    pub method: String,
+    pub path: String,
+    pub params: HashMap<String, String>,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub cookies: HashMap<String, String>,
+    pub body: Vec<u8>,
+    pub form: Fields,
+    pub files: Vec<FormFile>
+}
+
+impl PageRequest {
+    pub async fn read(req: Request<Body>, params: HashMap<String, String>) -> Result<Self, PageError> {
+        let (parts, body) = req.into_parts();
+
+        let content_length = parts.headers.get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        if content_length > MAX_BODY_SIZE {
+            return Err(PageError::PayloadTooLarge);
+        }
+
+        let body = read_body(body, MAX_BODY_SIZE).await
+            .map_err(|err| match err {
+                ReadBodyError::TooLarge => PageError::PayloadTooLarge,
+                ReadBodyError::Hyper(err) => PageError::internal(parts.uri.path(), err)
+            })?;
+
+        let mut headers: HashMap<String, String> = HashMap::new();
+        for (name, value) in &parts.headers {
+            let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+
+            headers.entry(name.as_str().to_string())
+                .and_modify(|v| {
+                    v.push_str(", ");
+                    v.push_str(&value);
+                })
+                .or_insert(value);
+        }
+
+        let cookies = parts.headers.get_all(header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(parse_cookies)
+            .collect();
+
+        let content_type = parts.headers.get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok());
+
+        let form = parse_form(content_type, &body)
+            .map_err(PageError::BadRequest)?
+            .unwrap_or_default();
+
+        Ok(Self {
+            method: parts.method.as_str().to_string(),
+            path: parts.uri.path().to_string(),
+            params: params,
+            query: parse_query(parts.uri.query()),
+            headers: headers,
+            cookies: cookies,
+            body: body,
+            form: form.fields,
+            files: form.files
+        })
+    }
+
+    pub fn to_value(&self) -> Value {
+        value::obj(Some("Request"), vec![
+            ("method", Value::Str(self.method.clone())),
+            ("path", Value::Str(self.path.clone())),
+            ("params", value::str_map(&self.params)),
+            ("query", value::str_map(&self.query)),
+            ("headers", value::str_map(&self.headers)),
+            ("cookies", value::str_map(&self.cookies)),
+            ("body", Value::Str(String::from_utf8_lossy(&self.body).to_string())),
+            ("form", value::fields_map(&self.form)),
+            ("files", Value::List(
+                self.files.iter()
+                    .map(|f| value::obj(Some("File"), vec![
+                        ("field", Value::Str(f.field.clone())),
+                        ("filename", Value::Str(f.filename.clone())),
+                        ("content_type", f.content_type.clone().map(Value::Str).unwrap_or(Value::None)),
+                        ("size", Value::Int(f.content.len() as i64)),
+                        ("content", Value::Str(String::from_utf8_lossy(&f.content).to_string()))
+                    ]))
+                    .collect()
+            ))
+        ])
+    }
+}
+
+pub enum ReadBodyError {
+    TooLarge,
+    Hyper(hyper::Error)
+}
+
+/// Reads `body` chunk by chunk and stops as soon as it is larger than
+/// `limit`, so a chunked body without a Content-Length can not use up
+/// memory.
+pub async fn read_body(mut body: Body, limit: usize) -> Result<Vec<u8>, ReadBodyError> {
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(ReadBodyError::Hyper)?;
+
+        if bytes.len() + chunk.len() > limit {
+            return Err(ReadBodyError::TooLarge);
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
+pub fn parse_cookies(header: &str) -> Vec<(String, String)> {
+    header.split(';')
+        .filter_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            let name = name.trim();
+
+            if name.is_empty() {
+                return None;
+            }
+
+            // Values set by `Cookie` are percent-encoded.
+            let value = percent_decode_str(value.trim().trim_matches('"')).decode_utf8_lossy();
+
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cookie_header() {
+        let cookies = parse_cookies("session=abc; theme=\"dark\";broken; =x");
+
+        assert_eq!(cookies, vec![
+            ("session".to_string(), "abc".to_string()),
+            ("theme".to_string(), "dark".to_string())
+        ]);
+    }
+
+    #[tokio::test]
+    async fn reads_request_parts() {
+        let req = Request::builder()
+            .method("POST")
+            .uri("/todo/1?filter=done")
+            .header("X-Test", "a")
+            .header("X-Test", "b")
+            .header("Cookie", "session=abc")
+            .body(Body::from("hello"))
+            .unwrap();
+
+        let params = vec![("id".to_string(), "1".to_string())].into_iter().collect();
+        let req = PageRequest::read(req, params).await.unwrap();
+
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/todo/1");
+        assert_eq!(req.params["id"], "1");
+        assert_eq!(req.query["filter"], "done");
+        assert_eq!(req.headers["x-test"], "a, b");
+        assert_eq!(req.cookies["session"], "abc");
+        assert_eq!(req.body, b"hello");
+    }
+
+    #[tokio::test]
+    async fn reads_form_body() {
+        let req = Request::builder()
+            .method("POST")
+            .uri("/todo")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(Body::from("name=Buy+milk"))
+            .unwrap();
+
+        let req = PageRequest::read(req, HashMap::new()).await.unwrap();
+
+        assert_eq!(req.form["name"], vec!["Buy milk"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_large_chunked_body() {
+        let chunks = (0..3).map(|_| Ok::<_, std::io::Error>(vec![0u8; MAX_BODY_SIZE / 2]));
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/upload")
+            .body(Body::wrap_stream(futures::stream::iter(chunks)))
+            .unwrap();
+
+        let res = PageRequest::read(req, HashMap::new()).await;
+
+        assert!(matches!(res, Err(PageError::PayloadTooLarge)));
+    }
+}
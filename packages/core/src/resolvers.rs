@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use flexscript::Value;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+use crate::store::Store;
+
+pub type Resolver = Arc<dyn Fn(Value) -> BoxFuture<'static, Value> + Send + Sync>;
+
+/// Resolves values a page awaits. Awaited objects are dispatched by their
+/// name, so `await Sleep { ms: 100 }` goes to the `Sleep` resolver. Values
+/// without a matching resolver resolve to themselves. `Sleep` and `Store`
+/// are always there.
+#[derive(Clone)]
+pub struct Resolvers {
+    map: HashMap<String, Resolver>
+}
+
+impl Resolvers {
+    pub fn new() -> Self {
+        let mut resolvers = Self {
+            map: HashMap::new()
+        };
+
+        resolvers.add("Sleep", |value| async move {
+            if let Value::Obj(obj) = &value {
+                for prop in &obj.props {
+                    if prop.name == "ms" {
+                        // Negative durations do not wait.
+                        let ms = match &prop.value {
+                            Value::Int(ms) => (*ms).max(0) as u64,
+                            Value::Float(ms) if ms.is_finite() => ms.max(0.0) as u64,
+                            _ => continue
+                        };
+
+                        tokio::time::sleep(Duration::from_millis(ms)).await;
+                    }
+                }
+            }
+
+            Value::None
+        });
+
+        let store = Store::default();
+
+        resolvers.add("Store", move |value| {
+            let store = store.clone();
+            async move { store.apply(value) }
+        });
+
+        resolvers
+    }
+
+    pub fn add<F, Fut>(&mut self, name: &str, f: F)
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Value> + Send + 'static
+    {
+        self.map.insert(name.to_string(), Arc::new(move |value| f(value).boxed()));
+    }
+
+    pub async fn resolve(&self, value: Value) -> Value {
+        let resolver = match &value {
+            Value::Obj(obj) => obj.name.as_ref()
+                .and_then(|name| self.map.get(name))
+                .cloned(),
+            _ => None
+        };
+
+        match resolver {
+            Some(resolver) => resolver(value).await,
+            None => value
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn plain_value_resolves_to_itself() {
+        let resolvers = Resolvers::new();
+
+        match resolvers.resolve(Value::Int(5)).await {
+            Value::Int(5) => {},
+            v => panic!("unexpected {:?}", v)
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatches_by_object_name() {
+        let mut resolvers = Resolvers::new();
+        resolvers.add("Query", |_| async { Value::Str("rows".to_string()) });
+
+        let res = resolvers.resolve(value::obj(Some("Query"), vec![])).await;
+
+        match res {
+            Value::Str(s) => assert_eq!(s, "rows"),
+            v => panic!("unexpected {:?}", v)
+        }
+    }
+
+    #[tokio::test]
+    async fn negative_sleep_does_not_wait() {
+        let resolvers = Resolvers::new();
+
+        let sleep = resolvers.resolve(value::obj(Some("Sleep"), vec![("ms", Value::Int(-1))]));
+        let res = tokio::time::timeout(Duration::from_secs(1), sleep).await;
+
+        assert!(res.is_ok());
+    }
+}
@@ -0,0 +1,401 @@
+use flexscript::Value;
+use hyper::StatusCode;
+use hyper::header;
+use hyper::http::response::Builder;
+use percent_encoding::AsciiSet;
+use percent_encoding::CONTROLS;
+use percent_encoding::utf8_percent_encode;
+
+/// Bytes that are not allowed in a cookie value, and `%` so encoded values
+/// can be told apart. See the cookie-octet rule of RFC 6265.
+const COOKIE_VALUE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b',')
+    .add(b';')
+    .add(b'\\')
+    .add(b'%');
+
+/// What a page asked to be sent back. Pages return an `Html` object, any
+/// other value which is sent as JSON, or a response descriptor:
+///
+/// ```text
+/// return Response {
+///     status: 201
+///     headers: { "x-request-id": "1" }
+///     cookies: { session: { value: "abc", http_only: true } }
+///     body: Html { ... }
+/// }
+///
+/// return Redirect { to: "/todo" }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PageResponse {
+    pub status: Option<StatusCode>,
+    pub headers: Vec<(String, String)>,
+    pub cookies: Vec<Cookie>,
+    pub body: ResponseBody
+}
+
+#[derive(Debug, Clone)]
+pub enum ResponseBody {
+    Html(Value),
+    Json(Value),
+    Text(String),
+    Empty
+}
+
+impl PageResponse {
+    pub fn body(body: ResponseBody) -> Self {
+        Self {
+            status: None,
+            headers: vec![],
+            cookies: vec![],
+            body: body
+        }
+    }
+
+    pub fn redirect(to: &str, status: StatusCode) -> Self {
+        Self {
+            status: Some(status),
+            headers: vec![(header::LOCATION.to_string(), to.to_string())],
+            cookies: vec![],
+            body: ResponseBody::Empty
+        }
+    }
+
+    /// Response builder with status, headers and cookies applied. The
+    /// caller renders the body.
+    pub fn builder(&self) -> Builder {
+        let mut builder = hyper::Response::builder()
+            .status(self.status.unwrap_or(StatusCode::OK));
+
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+
+        for cookie in &self.cookies {
+            builder = builder.header(header::SET_COOKIE, cookie.to_string());
+        }
+
+        builder
+    }
+}
+
+impl TryFrom<Value> for PageResponse {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let obj = match &value {
+            Value::Obj(obj) => obj,
+            _ => return Ok(PageResponse::body(ResponseBody::from(value)))
+        };
+
+        match obj.name.as_deref() {
+            Some("Redirect") => {
+                let mut to = None;
+                let mut status = StatusCode::SEE_OTHER;
+
+                for prop in &obj.props {
+                    match prop.name.as_ref() {
+                        "to" => to = Some(as_str(&prop.value, "to")?),
+                        "status" => status = as_status(&prop.value)?,
+                        _ => return Err(format!("unknown Redirect property {}", prop.name))
+                    }
+                }
+
+                match to {
+                    Some(to) => Ok(PageResponse::redirect(&to, status)),
+                    None => Err("Redirect is missing to".to_string())
+                }
+            },
+            Some("Response") => {
+                let mut res = PageResponse {
+                    status: None,
+                    headers: vec![],
+                    cookies: vec![],
+                    body: ResponseBody::Empty
+                };
+
+                for prop in &obj.props {
+                    match prop.name.as_ref() {
+                        "status" => res.status = Some(as_status(&prop.value)?),
+                        "headers" => {
+                            for (name, value) in as_props(&prop.value, "headers")? {
+                                res.headers.push((name, as_str(&value, "headers")?));
+                            }
+                        },
+                        "cookies" => {
+                            for (name, value) in as_props(&prop.value, "cookies")? {
+                                res.cookies.push(Cookie::from_value(name, &value)?);
+                            }
+                        },
+                        "redirect" => {
+                            let to = as_str(&prop.value, "redirect")?;
+                            res.headers.push((header::LOCATION.to_string(), to));
+
+                            if res.status.is_none() {
+                                res.status = Some(StatusCode::SEE_OTHER);
+                            }
+                        },
+                        "body" => {
+                            res.body = match &prop.value {
+                                Value::Str(s) => ResponseBody::Text(s.clone()),
+                                Value::None => ResponseBody::Empty,
+                                v => ResponseBody::from(v.clone())
+                            };
+                        },
+                        _ => return Err(format!("unknown Response property {}", prop.name))
+                    }
+                }
+
+                Ok(res)
+            },
+            _ => Ok(PageResponse::body(ResponseBody::from(value)))
+        }
+    }
+}
+
+impl From<Value> for ResponseBody {
+    fn from(value: Value) -> Self {
+        match &value {
+            Value::Obj(obj) if obj.name.as_deref() == Some("Html") => ResponseBody::Html(value),
+            _ => ResponseBody::Json(value)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub path: Option<String>,
+    pub max_age: Option<i64>,
+    pub http_only: bool,
+    pub secure: bool,
+    pub same_site: Option<String>
+}
+
+impl Cookie {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+            path: None,
+            max_age: None,
+            http_only: false,
+            secure: false,
+            same_site: None
+        }
+    }
+
+    /// Cookie from either a plain string value or an object with `value`,
+    /// `path`, `max_age`, `http_only`, `secure` and `same_site`.
+    fn from_value(name: String, value: &Value) -> Result<Self, String> {
+        if !is_token(&name) {
+            return Err(format!("invalid cookie name {:?}", name));
+        }
+
+        let cookie = match value {
+            Value::Str(s) => Cookie::new(&name, s),
+            Value::Obj(obj) => {
+                let mut cookie = Cookie::new(&name, "");
+
+                for prop in &obj.props {
+                    match prop.name.as_ref() {
+                        "value" => cookie.value = as_str(&prop.value, "cookie value")?,
+                        "path" => cookie.path = Some(as_str(&prop.value, "cookie path")?),
+                        "max_age" => {
+                            cookie.max_age = match &prop.value {
+                                Value::Int(i) => Some(*i as i64),
+                                v => return Err(format!("cookie max_age must be a number, got {:?}", v))
+                            }
+                        },
+                        "http_only" => cookie.http_only = as_bool(&prop.value, "cookie http_only")?,
+                        "secure" => cookie.secure = as_bool(&prop.value, "cookie secure")?,
+                        "same_site" => cookie.same_site = Some(as_str(&prop.value, "cookie same_site")?),
+                        _ => return Err(format!("unknown cookie property {}", prop.name))
+                    }
+                }
+
+                cookie
+            },
+            v => return Err(format!("cookie {} must be a string or object, got {:?}", name, v))
+        };
+
+        // Written into the header as they are.
+        if let Some(path) = &cookie.path {
+            if path.chars().any(|c| c == ';' || c.is_control()) {
+                return Err(format!("invalid cookie path {:?}", path));
+            }
+        }
+
+        if let Some(same_site) = &cookie.same_site {
+            if !matches!(same_site.as_str(), "Strict" | "Lax" | "None") {
+                return Err(format!("cookie same_site must be Strict, Lax or None, got {:?}", same_site));
+            }
+        }
+
+        Ok(cookie)
+    }
+}
+
+impl ToString for Cookie {
+    fn to_string(&self) -> String {
+        // The value is percent-encoded, so it can not end the cookie or add
+        // attributes. `parse_cookies` decodes it again.
+        let mut s = format!("{}={}", self.name, utf8_percent_encode(&self.value, COOKIE_VALUE));
+
+        if let Some(path) = &self.path {
+            s.push_str(&format!("; Path={}", path));
+        }
+
+        if let Some(max_age) = self.max_age {
+            s.push_str(&format!("; Max-Age={}", max_age));
+        }
+
+        if self.http_only {
+            s.push_str("; HttpOnly");
+        }
+
+        if self.secure {
+            s.push_str("; Secure");
+        }
+
+        if let Some(same_site) = &self.same_site {
+            s.push_str(&format!("; SameSite={}", same_site));
+        }
+
+        s
+    }
+}
+
+/// Whether `s` is a token as defined by RFC 7230, which cookie names must be.
+fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| {
+        c.is_ascii_graphic() && !"()<>@,;:\\\"/[]?={}".contains(c)
+    })
+}
+
+fn as_str(value: &Value, name: &str) -> Result<String, String> {
+    match value {
+        Value::Str(s) => Ok(s.clone()),
+        Value::Int(i) => Ok(i.to_string()),
+        v => Err(format!("{} must be a string, got {:?}", name, v))
+    }
+}
+
+fn as_bool(value: &Value, name: &str) -> Result<bool, String> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        v => Err(format!("{} must be a boolean, got {:?}", name, v))
+    }
+}
+
+fn as_status(value: &Value) -> Result<StatusCode, String> {
+    match value {
+        Value::Int(i) => u16::try_from(*i).ok()
+            .and_then(|code| StatusCode::from_u16(code).ok())
+            .ok_or_else(|| format!("invalid status {}", i)),
+        v => Err(format!("status must be a number, got {:?}", v))
+    }
+}
+
+fn as_props(value: &Value, name: &str) -> Result<Vec<(String, Value)>, String> {
+    match value {
+        Value::Obj(obj) => Ok(
+            obj.props.iter()
+                .map(|p| (p.name.clone(), p.value.clone()))
+                .collect()
+        ),
+        v => Err(format!("{} must be an object, got {:?}", name, v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value;
+
+    use super::*;
+
+    #[test]
+    fn html_value_is_html_response() {
+        let res = PageResponse::try_from(value::obj(Some("Html"), vec![])).unwrap();
+
+        assert_eq!(res.status, None);
+        assert!(matches!(res.body, ResponseBody::Html(_)));
+    }
+
+    #[test]
+    fn other_values_are_json_responses() {
+        let res = PageResponse::try_from(Value::List(vec![Value::Int(1)])).unwrap();
+        assert!(matches!(res.body, ResponseBody::Json(_)));
+
+        let res = PageResponse::try_from(value::obj(None, vec![])).unwrap();
+        assert!(matches!(res.body, ResponseBody::Json(_)));
+    }
+
+    #[test]
+    fn redirect_sets_location() {
+        let res = PageResponse::try_from(value::obj(Some("Redirect"), vec![
+            ("to", Value::Str("/todo".to_string()))
+        ])).unwrap();
+
+        assert_eq!(res.status, Some(StatusCode::SEE_OTHER));
+        assert_eq!(res.headers, vec![("location".to_string(), "/todo".to_string())]);
+    }
+
+    #[test]
+    fn response_with_cookies() {
+        let res = PageResponse::try_from(value::obj(Some("Response"), vec![
+            ("status", Value::Int(201)),
+            ("cookies", value::obj(None, vec![
+                ("theme", Value::Str("dark".to_string())),
+                ("session", value::obj(None, vec![
+                    ("value", Value::Str("abc".to_string())),
+                    ("path", Value::Str("/".to_string())),
+                    ("http_only", Value::Bool(true))
+                ]))
+            ])),
+            ("body", Value::Str("created".to_string()))
+        ])).unwrap();
+
+        assert_eq!(res.status, Some(StatusCode::CREATED));
+        assert_eq!(
+            res.cookies.iter().map(|c| c.to_string()).collect::<Vec<String>>(),
+            vec!["theme=dark", "session=abc; Path=/; HttpOnly"]
+        );
+        assert!(matches!(res.body, ResponseBody::Text(ref s) if s == "created"));
+    }
+
+    #[test]
+    fn unknown_response_property_is_an_error() {
+        let res = PageResponse::try_from(value::obj(Some("Response"), vec![
+            ("stauts", Value::Int(200))
+        ]));
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn cookie_values_can_not_add_attributes() {
+        let cookie = Cookie::new("session", "abc; Domain=evil.example\n");
+        assert_eq!(cookie.to_string(), "session=abc%3B%20Domain=evil.example%0A");
+
+        let res = PageResponse::try_from(value::obj(Some("Response"), vec![
+            ("cookies", value::obj(None, vec![
+                ("a;b", Value::Str("x".to_string()))
+            ]))
+        ]));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn out_of_range_status_is_an_error() {
+        let res = PageResponse::try_from(value::obj(Some("Response"), vec![
+            ("status", Value::Int(65736))
+        ]));
+
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::server::conn;
+use hyper::service::service_fn;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::sync::mpsc;
+
+use crate::monolith::App;
+use crate::monolith::Reloader;
+use crate::shutdown::ShutdownHandle;
+use crate::shutdown::ShutdownSignal;
+
+/// Wait after a failed accept. Errors like running out of file
+/// descriptors would otherwise fail again right away.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Where the server listens.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    Unix(PathBuf)
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddr::Tcp(addr) => write!(f, "http://{}", addr),
+            ListenAddr::Unix(path) => write!(f, "unix:{}", path.display())
+        }
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf)
+}
+
+/// Removes the socket file, so it is not left behind once the server
+/// stops.
+#[cfg(unix)]
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Listener::Unix(_, path) = &*self {
+            if let Err(err) = std::fs::remove_file(path) {
+                log::warn!("failed to remove {}: {}", path.display(), err);
+            }
+        }
+    }
+}
+
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+impl Listener {
+    async fn accept(&self) -> std::io::Result<Box<dyn Io>> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, addr) = listener.accept().await?;
+                log::debug!("new client connected {}", addr);
+
+                Ok(Box::new(stream))
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => {
+                let (stream, _) = listener.accept().await?;
+                log::debug!("new client connected");
+
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+/// Bound server, returned by `Monolith::bind`. The socket is already
+/// listening, so `local_addr` reports the real port when port 0 was asked.
+pub struct Server {
+    listener: Listener,
+    app: Arc<App>,
+    shutdown: ShutdownHandle
+}
+
+impl Server {
+    pub(crate) async fn bind_tcp(host: &str, port: u16, app: App) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind((host, port)).await?;
+
+        Ok(Self::new(Listener::Tcp(listener), app))
+    }
+
+    #[cfg(unix)]
+    pub(crate) async fn bind_unix(path: PathBuf, app: App) -> anyhow::Result<Self> {
+        use std::os::unix::fs::FileTypeExt;
+
+        // A socket file left behind by a previous run would fail the bind.
+        if let Ok(meta) = std::fs::symlink_metadata(&path) {
+            if meta.file_type().is_socket() {
+                std::fs::remove_file(&path)?;
+            }
+        }
+
+        let listener = UnixListener::bind(&path)?;
+
+        Ok(Self::new(Listener::Unix(listener, path), app))
+    }
+
+    fn new(listener: Listener, app: App) -> Self {
+        Self {
+            listener: listener,
+            app: Arc::new(app),
+            shutdown: ShutdownHandle::new()
+        }
+    }
+
+    pub fn local_addr(&self) -> ListenAddr {
+        match &self.listener {
+            Listener::Tcp(listener) => ListenAddr::Tcp(listener.local_addr().unwrap()),
+            #[cfg(unix)]
+            Listener::Unix(_, path) => ListenAddr::Unix(path.clone())
+        }
+    }
+
+    /// Handle for stopping the server from elsewhere, for example a test.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Handle for replacing routes while the server runs.
+    pub fn reloader(&self) -> Reloader {
+        Reloader::new(self.app.clone())
+    }
+
+    /// Serves until shut down through a `ShutdownHandle`.
+    pub async fn run(self) {
+        self.run_until(futures::future::pending()).await
+    }
+
+    /// Serves until `signal` completes or a `ShutdownHandle` is used. On
+    /// shutdown the listener is closed, in-flight requests get to finish
+    /// and websocket clients are sent a close frame. Whatever is still
+    /// running after the shutdown timeout is abandoned.
+    pub async fn run_until(self, signal: impl Future<Output = ()>) {
+        log::info!("listening {}", self.local_addr());
+
+        let (in_flight_tx, mut in_flight_rx) = mpsc::channel::<()>(1);
+        let mut stop = self.shutdown.signal(in_flight_tx.clone());
+
+        tokio::pin!(signal);
+
+        loop {
+            tokio::select! {
+                _ = &mut signal => {
+                    self.shutdown.shutdown();
+
+                    break;
+                },
+                _ = stop.wait() => {
+                    break;
+                },
+                res = self.listener.accept() => {
+                    match res {
+                        Ok(stream) => {
+                            let signal = self.shutdown.signal(in_flight_tx.clone());
+
+                            spawn_connection(stream, self.app.clone(), signal);
+                        },
+                        Err(err) => {
+                            log::error!("accept error: {}", err);
+
+                            tokio::time::sleep(ACCEPT_BACKOFF).await;
+                        }
+                    }
+                }
+            }
+        }
+
+        log::info!("shutting down");
+
+        drop(stop);
+        drop(in_flight_tx);
+        drop(self.listener);
+
+        match tokio::time::timeout(self.app.shutdown_timeout, in_flight_rx.recv()).await {
+            Ok(_) => log::info!("shutdown complete"),
+            Err(_) => log::warn!(
+                "shutdown timeout of {:?} reached, dropping open connections",
+                self.app.shutdown_timeout
+            )
+        }
+    }
+}
+
+fn spawn_connection(stream: Box<dyn Io>, app: Arc<App>, mut signal: ShutdownSignal) {
+    tokio::spawn(async move {
+        let service_signal = signal.clone();
+        let service = service_fn(move |req| {
+            let app = app.clone();
+            let signal = service_signal.clone();
+
+            async move {
+                Ok::<_, anyhow::Error>(app.handle(req, signal).await)
+            }
+        });
+
+        let conn = conn::Http::new()
+            .serve_connection(stream, service)
+            .with_upgrades();
+
+        tokio::pin!(conn);
+
+        let res = tokio::select! {
+            res = conn.as_mut() => res,
+            _ = signal.wait() => {
+                conn.as_mut().graceful_shutdown();
+                conn.await
+            }
+        };
+
+        match res {
+            Ok(_) => {},
+            Err(err) => {
+                log::error!("connection error: {}", err);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+    use hyper::Client;
+    use hyper::Request;
+    use hyper::header;
+    use hyper_tungstenite::WebSocketStream;
+    use hyper_tungstenite::tungstenite::Message;
+    use hyper_tungstenite::tungstenite::protocol::Role;
+    use hyper_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+    use crate::Monolith;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn binds_ephemeral_port() {
+        let server = Monolith::new()
+            .listen(0)
+            .add("/", "return [1, 2]")
+            .bind()
+            .await
+            .unwrap();
+
+        let addr = match server.local_addr() {
+            ListenAddr::Tcp(addr) => addr,
+            addr => panic!("unexpected {}", addr)
+        };
+
+        assert_ne!(addr.port(), 0);
+
+        let handle = server.shutdown_handle();
+        let running = tokio::spawn(server.run());
+
+        let uri = format!("http://{}/", addr).parse().unwrap();
+        let res = Client::new().get(uri).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+
+        assert_eq!(&body[..], b"[1,2]");
+
+        handle.shutdown();
+        running.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn closes_ui_clients_on_shutdown() {
+        let (clients_tx, mut clients_rx) = mpsc::unbounded_channel();
+
+        let server = Monolith::new()
+            .listen(0)
+            .ui_clients(clients_tx)
+            .bind()
+            .await
+            .unwrap();
+
+        let addr = match server.local_addr() {
+            ListenAddr::Tcp(addr) => addr,
+            addr => panic!("unexpected {}", addr)
+        };
+
+        let handle = server.shutdown_handle();
+        let running = tokio::spawn(server.run());
+
+        let req = Request::get(format!("http://{}/ui", addr))
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+            .body(hyper::Body::empty())
+            .unwrap();
+
+        let res = Client::new().request(req).await.unwrap();
+        assert_eq!(res.status(), hyper::StatusCode::SWITCHING_PROTOCOLS);
+
+        let upgraded = hyper::upgrade::on(res).await.unwrap();
+        let mut ws = WebSocketStream::from_raw_socket(upgraded, Role::Client, None).await;
+
+        // Held so the worker keeps running until shutdown.
+        let _client = clients_rx.recv().await.unwrap();
+
+        handle.shutdown();
+
+        match ws.next().await {
+            Some(Ok(Message::Close(Some(frame)))) => assert_eq!(frame.code, CloseCode::Away),
+            msg => panic!("expected close frame, got {:?}", msg)
+        }
+
+        drop(ws);
+        running.await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn removes_unix_socket_on_shutdown() {
+        let path = std::env::temp_dir().join(format!("monolith-{}.sock", std::process::id()));
+
+        let server = Monolith::new()
+            .unix_socket(&path)
+            .bind()
+            .await
+            .unwrap();
+
+        assert!(path.exists());
+
+        let handle = server.shutdown_handle();
+        let running = tokio::spawn(server.run());
+
+        handle.shutdown();
+        running.await.unwrap();
+
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+
+/// Tells a running `Server` to stop. Cloning is cheap and every clone
+/// controls the same server.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    tx: Arc<watch::Sender<bool>>
+}
+
+impl ShutdownHandle {
+    pub(crate) fn new() -> Self {
+        let (tx, _) = watch::channel(false);
+
+        Self {
+            tx: Arc::new(tx)
+        }
+    }
+
+    pub fn shutdown(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    pub(crate) fn signal(&self, in_flight: mpsc::Sender<()>) -> ShutdownSignal {
+        ShutdownSignal {
+            rx: self.tx.subscribe(),
+            _in_flight: in_flight
+        }
+    }
+}
+
+/// Held by connections and websocket workers. The server waits for every
+/// signal to be dropped before it finishes shutting down.
+#[derive(Clone)]
+pub struct ShutdownSignal {
+    rx: watch::Receiver<bool>,
+    _in_flight: mpsc::Sender<()>
+}
+
+impl ShutdownSignal {
+    /// Completes once shutdown has been requested.
+    pub async fn wait(&mut self) {
+        while !*self.rx.borrow() {
+            if self.rx.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        *self.rx.borrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_for_in_flight_signals() {
+        let handle = ShutdownHandle::new();
+        let (in_flight_tx, mut in_flight_rx) = mpsc::channel(1);
+
+        let mut signal = handle.signal(in_flight_tx.clone());
+        drop(in_flight_tx);
+
+        let worker = tokio::spawn(async move {
+            signal.wait().await;
+            assert!(signal.is_shutdown());
+        });
+
+        handle.shutdown();
+        worker.await.unwrap();
+
+        let done = tokio::time::timeout(Duration::from_secs(1), in_flight_rx.recv()).await;
+        assert_eq!(done, Ok(None));
+    }
+}
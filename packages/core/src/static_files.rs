@@ -0,0 +1,476 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io::SeekFrom;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use hyper::Body;
+use hyper::HeaderMap;
+use hyper::Method;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
+use hyper::header;
+use percent_encoding::percent_decode_str;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeekExt;
+
+/// Size of the chunks files are streamed in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// File compiled into the binary, for single binary deploys.
+///
+/// ```ignore
+/// const ASSETS: &[EmbeddedAsset] = &[
+///     EmbeddedAsset { path: "app.css", content: include_bytes!("../public/app.css") }
+/// ];
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedAsset {
+    pub path: &'static str,
+    pub content: &'static [u8]
+}
+
+#[derive(Debug, Clone)]
+enum StaticSource {
+    Dir(PathBuf),
+    Embedded(&'static [EmbeddedAsset])
+}
+
+/// Files served under a url prefix.
+#[derive(Debug, Clone)]
+pub struct StaticMount {
+    prefix: String,
+    source: StaticSource
+}
+
+impl StaticMount {
+    pub fn dir(prefix: &str, path: impl Into<PathBuf>) -> Self {
+        Self {
+            prefix: normalize_prefix(prefix),
+            source: StaticSource::Dir(path.into())
+        }
+    }
+
+    pub fn embedded(prefix: &str, assets: &'static [EmbeddedAsset]) -> Self {
+        Self {
+            prefix: normalize_prefix(prefix),
+            source: StaticSource::Embedded(assets)
+        }
+    }
+
+    /// Serves the file `req` points to. Returns `None` when the request is
+    /// outside the prefix or no such file exists, so routing can go on.
+    pub async fn serve(&self, req: &Request<Body>) -> Option<Response<Body>> {
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            return None;
+        }
+
+        let rel = req.uri().path().strip_prefix(&self.prefix)?;
+
+        if !self.prefix.ends_with('/') && !rel.is_empty() && !rel.starts_with('/') {
+            return None;
+        }
+
+        let rel = sanitize(rel)?;
+
+        match &self.source {
+            StaticSource::Dir(root) => {
+                let mut path = root.join(&rel);
+
+                if tokio::fs::metadata(&path).await.ok()?.is_dir() {
+                    path = path.join("index.html");
+                }
+
+                let file = tokio::fs::File::open(&path).await.ok()?;
+                let meta = file.metadata().await.ok()?;
+
+                if !meta.is_file() {
+                    return None;
+                }
+
+                let modified = meta.modified().ok();
+                let mtime = modified
+                    .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                let etag = format!(r#"W/"{:x}-{:x}""#, meta.len(), mtime);
+
+                Some(respond(req.headers(), &path, Content::File(file, meta.len()), etag, modified).await)
+            },
+            StaticSource::Embedded(assets) => {
+                let rel = rel.to_string_lossy().replace('\\', "/");
+                let rel = match rel.as_str() {
+                    "" => "index.html".to_string(),
+                    _ => rel
+                };
+
+                let asset = assets.iter()
+                    .find(|a| a.path.trim_start_matches('/') == rel)?;
+
+                let mut hasher = DefaultHasher::new();
+                asset.content.hash(&mut hasher);
+                let etag = format!(r#""{:x}""#, hasher.finish());
+
+                Some(respond(req.headers(), Path::new(&rel), Content::Embedded(asset.content), etag, None).await)
+            }
+        }
+    }
+
+    /// Copies the mounted files under `out_dir`, at the path they are
+    /// served from.
+    pub fn export(&self, out_dir: &Path) -> std::io::Result<()> {
+        let target = out_dir.join(self.prefix.trim_start_matches('/'));
+
+        match &self.source {
+            StaticSource::Dir(root) => copy_dir(root, &target),
+            StaticSource::Embedded(assets) => {
+                for asset in assets.iter() {
+                    let rel = match sanitize(asset.path) {
+                        Some(rel) => rel,
+                        None => continue
+                    };
+                    let path = target.join(rel);
+
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+
+                    std::fs::write(path, asset.content)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn normalize_prefix(prefix: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+
+    match prefix.starts_with('/') {
+        true => prefix.to_string(),
+        false => format!("/{}", prefix)
+    }
+}
+
+/// Decodes the request path and rejects anything that could escape the
+/// served directory.
+fn sanitize(rel: &str) -> Option<PathBuf> {
+    let decoded = percent_decode_str(rel).decode_utf8().ok()?;
+    let mut path = PathBuf::new();
+
+    for component in Path::new(decoded.as_ref()).components() {
+        match component {
+            Component::Normal(c) => path.push(c),
+            Component::RootDir | Component::CurDir => {},
+            Component::ParentDir | Component::Prefix(_) => return None
+        }
+    }
+
+    Some(path)
+}
+
+/// File to respond with. Files on disk are streamed, so only the requested
+/// range is read.
+enum Content {
+    File(tokio::fs::File, u64),
+    Embedded(&'static [u8])
+}
+
+impl Content {
+    fn len(&self) -> u64 {
+        match self {
+            Content::File(_, len) => *len,
+            Content::Embedded(content) => content.len() as u64
+        }
+    }
+
+    /// Body with the bytes from `start` to `end` inclusive, or all of them.
+    async fn body(self, range: Option<(u64, u64)>) -> std::io::Result<Body> {
+        match self {
+            Content::File(mut file, len) => {
+                let len = match range {
+                    Some((start, end)) => {
+                        file.seek(SeekFrom::Start(start)).await?;
+                        end - start + 1
+                    },
+                    None => len
+                };
+
+                Ok(file_body(file, len))
+            },
+            Content::Embedded(content) => match range {
+                Some((start, end)) => Ok(Body::from(&content[start as usize..=end as usize])),
+                None => Ok(Body::from(content))
+            }
+        }
+    }
+}
+
+/// Streams the next `len` bytes of `file`.
+fn file_body(file: tokio::fs::File, len: u64) -> Body {
+    let chunks = futures::stream::unfold((file, len), |(mut file, left)| async move {
+        if left == 0 {
+            return None;
+        }
+
+        let mut chunk = vec![0; left.min(CHUNK_SIZE as u64) as usize];
+
+        match file.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(n) => {
+                chunk.truncate(n);
+                Some((Ok(chunk), (file, left - n as u64)))
+            },
+            Err(err) => Some((Err(err), (file, 0)))
+        }
+    });
+
+    Body::wrap_stream(chunks)
+}
+
+async fn respond(
+    headers: &HeaderMap,
+    path: &Path,
+    content: Content,
+    etag: String,
+    modified: Option<SystemTime>
+) -> Response<Body> {
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, mime.as_ref())
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, &etag);
+
+    if let Some(modified) = modified {
+        builder = builder.header(header::LAST_MODIFIED, httpdate::fmt_http_date(modified));
+    }
+
+    if is_not_modified(headers, &etag, modified) {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    let range = headers.get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| if_range_matches(headers, &etag));
+
+    let len = content.len();
+
+    let (range, builder) = match range.map(|r| parse_range(r, len)) {
+        Some(Ok(Some((start, end)))) => {
+            let builder = builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len));
+
+            (Some((start, end)), builder)
+        },
+        Some(Err(())) => {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Body::empty())
+                .unwrap();
+        },
+        _ => (None, builder)
+    };
+
+    match content.body(range).await {
+        Ok(body) => builder.body(body).unwrap(),
+        Err(err) => {
+            log::error!("failed to read {}: {}", path.display(), err);
+
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap()
+        }
+    }
+}
+
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    if let Some(none_match) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        return none_match.split(',')
+            .map(|t| t.trim())
+            .any(|t| t == "*" || t.trim_start_matches("W/") == etag.trim_start_matches("W/"));
+    }
+
+    let since = headers.get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok());
+
+    match (since, modified) {
+        (Some(since), Some(modified)) => {
+            // Http dates have second precision.
+            let modified = modified.duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs());
+            let since = since.duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs());
+
+            matches!((modified, since), (Ok(m), Ok(s)) if m <= s)
+        },
+        _ => false
+    }
+}
+
+/// If-Range takes a strong comparison, so with a weak `etag`, like the
+/// ones of files on disk, the range is ignored and the whole file sent.
+fn if_range_matches(headers: &HeaderMap, etag: &str) -> bool {
+    match headers.get(header::IF_RANGE).and_then(|v| v.to_str().ok()) {
+        Some(if_range) => !etag.starts_with("W/") && if_range == etag,
+        None => true
+    }
+}
+
+/// Parses a single `bytes=` range into inclusive offsets. `Ok(None)` means
+/// the header is ignored and the whole file is sent, which is what we do
+/// for multiple ranges.
+fn parse_range(range: &str, len: u64) -> Result<Option<(u64, u64)>, ()> {
+    let spec = match range.strip_prefix("bytes=") {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return Ok(None)
+    };
+
+    let (start, end) = spec.split_once('-').ok_or(())?;
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix = suffix.parse::<u64>().map_err(|_| ())?;
+
+            if suffix == 0 {
+                return Err(());
+            }
+
+            (len.saturating_sub(suffix), len.saturating_sub(1))
+        },
+        (start, "") => (start.parse::<u64>().map_err(|_| ())?, len.saturating_sub(1)),
+        (start, end) => (
+            start.parse::<u64>().map_err(|_| ())?,
+            end.parse::<u64>().map_err(|_| ())?.min(len.saturating_sub(1))
+        )
+    };
+
+    if len == 0 || start > end || start >= len {
+        return Err(());
+    }
+
+    Ok(Some((start, end)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASSETS: &[EmbeddedAsset] = &[
+        EmbeddedAsset { path: "app.css", content: b"body { margin: 0; }" },
+        EmbeddedAsset { path: "index.html", content: b"<html></html>" }
+    ];
+
+    fn get(uri: &str, headers: &[(&str, &str)]) -> Request<Body> {
+        let mut req = Request::builder().uri(uri);
+
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+
+        req.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("bytes=0-4", 10), Ok(Some((0, 4))));
+        assert_eq!(parse_range("bytes=5-", 10), Ok(Some((5, 9))));
+        assert_eq!(parse_range("bytes=-3", 10), Ok(Some((7, 9))));
+        assert_eq!(parse_range("bytes=8-20", 10), Ok(Some((8, 9))));
+        assert_eq!(parse_range("bytes=0-1,4-5", 10), Ok(None));
+        assert_eq!(parse_range("bytes=10-", 10), Err(()));
+    }
+
+    #[test]
+    fn rejects_parent_dirs() {
+        assert_eq!(sanitize("/css/app.css"), Some(PathBuf::from("css/app.css")));
+        assert_eq!(sanitize("/../secret"), None);
+        assert_eq!(sanitize("/%2e%2e/secret"), None);
+    }
+
+    #[tokio::test]
+    async fn serves_embedded_assets() {
+        let mount = StaticMount::embedded("/assets", ASSETS);
+
+        let res = mount.serve(&get("/assets/app.css", &[])).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/css");
+
+        let res = mount.serve(&get("/assets/", &[])).await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/html");
+
+        assert!(mount.serve(&get("/assets/missing.js", &[])).await.is_none());
+        assert!(mount.serve(&get("/assetsx/app.css", &[])).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn etag_and_range() {
+        let mount = StaticMount::embedded("/", ASSETS);
+
+        let res = mount.serve(&get("/app.css", &[])).await.unwrap();
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        let res = mount.serve(&get("/app.css", &[("If-None-Match", &etag)])).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let res = mount.serve(&get("/app.css", &[("Range", "bytes=0-3")])).await.unwrap();
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.headers()[header::CONTENT_RANGE], "bytes 0-3/19");
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"body");
+    }
+
+    #[tokio::test]
+    async fn streams_ranges_of_files() {
+        let dir = std::env::temp_dir().join(format!("monolith-static-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("data.txt"), b"0123456789").unwrap();
+
+        let mount = StaticMount::dir("/", &dir);
+
+        let res = mount.serve(&get("/data.txt", &[("Range", "bytes=3-5")])).await.unwrap();
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.headers()[header::CONTENT_RANGE], "bytes 3-5/10");
+
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"345");
+
+        // The etag is weak, so it never matches If-Range.
+        let res = mount.serve(&get("/data.txt", &[("Range", "bytes=3-5"), ("If-Range", &etag)])).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"0123456789");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
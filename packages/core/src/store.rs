@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use flexscript::Obj;
+use flexscript::ObjProp;
+use flexscript::Value;
+
+/// Collections of objects kept in memory for `await Store { .. }`, until
+/// the server stops. Every added object gets a string `id`.
+///
+/// ```text
+/// Store { collection: "items" }                           // list
+/// Store { collection: "items", add: { name: "milk" } }    // add, returns it
+/// Store { collection: "items", id: "1" }                  // one or None
+/// Store { collection: "items", id: "1", update: { .. } }  // set properties
+/// Store { collection: "items", id: "1", remove: true }    // remove
+/// ```
+#[derive(Clone, Default)]
+pub struct Store {
+    collections: Arc<Mutex<HashMap<String, Collection>>>
+}
+
+#[derive(Default)]
+struct Collection {
+    last_id: u64,
+    items: Vec<Obj>
+}
+
+impl Store {
+    pub fn apply(&self, value: Value) -> Value {
+        let props = match value {
+            Value::Obj(obj) => obj.props,
+            _ => return Value::None
+        };
+
+        let mut name = None;
+        let mut id = None;
+        let mut add = None;
+        let mut update = vec![];
+        let mut remove = false;
+
+        for prop in props {
+            match (prop.name.as_str(), prop.value) {
+                ("collection", Value::Str(s)) => name = Some(s),
+                ("id", Value::Str(s)) => id = Some(s),
+                ("id", Value::Int(i)) => id = Some(i.to_string()),
+                ("add", Value::Obj(obj)) => add = Some(obj),
+                ("update", Value::Obj(obj)) => update = obj.props,
+                ("remove", Value::Bool(b)) => remove = b,
+                (other, value) => log::warn!("Store: ignored {} {:?}", other, value)
+            }
+        }
+
+        let name = match name {
+            Some(name) => name,
+            None => {
+                log::warn!("Store without a collection");
+                return Value::None;
+            }
+        };
+
+        let mut collections = self.collections.lock().unwrap();
+        let collection = collections.entry(name).or_default();
+
+        if let Some(mut item) = add {
+            collection.last_id += 1;
+            set_prop(&mut item, "id", Value::Str(collection.last_id.to_string()));
+            collection.items.push(item.clone());
+
+            return Value::Obj(item);
+        }
+
+        let id = match id {
+            Some(id) => id,
+            None => return Value::List(collection.items.iter().cloned().map(Value::Obj).collect())
+        };
+
+        let inx = match collection.items.iter().position(|item| has_id(item, &id)) {
+            Some(inx) => inx,
+            None => return Value::None
+        };
+
+        if remove {
+            return Value::Obj(collection.items.remove(inx));
+        }
+
+        let item = &mut collection.items[inx];
+
+        for p in update.into_iter().filter(|p| p.name != "id") {
+            set_prop(item, &p.name, p.value);
+        }
+
+        Value::Obj(item.clone())
+    }
+}
+
+fn has_id(item: &Obj, id: &str) -> bool {
+    item.props.iter().any(|p| p.name == "id" && matches!(&p.value, Value::Str(s) if s == id))
+}
+
+fn set_prop(obj: &mut Obj, name: &str, value: Value) {
+    match obj.props.iter_mut().find(|p| p.name == name) {
+        Some(p) => p.value = value,
+        None => obj.props.push(ObjProp {
+            name: name.to_string(),
+            value: value
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::obj;
+
+    use super::*;
+
+    fn text(s: &str) -> Value {
+        Value::Str(s.to_string())
+    }
+
+    fn store(store: &Store, mut props: Vec<(&str, Value)>) -> String {
+        props.insert(0, ("collection", text("items")));
+
+        format!("{:?}", store.apply(obj(Some("Store"), props)))
+    }
+
+    #[test]
+    fn adds_updates_and_removes() {
+        let s = Store::default();
+
+        let milk = store(&s, vec![("add", obj(None, vec![("name", text("milk"))]))]);
+        assert_eq!(milk, format!("{:?}", obj(None, vec![("name", text("milk")), ("id", text("1"))])));
+
+        store(&s, vec![("add", obj(None, vec![("name", text("eggs"))]))]);
+        store(&s, vec![("id", text("1")), ("update", obj(None, vec![("name", text("oat milk"))]))]);
+        store(&s, vec![("id", text("2")), ("remove", Value::Bool(true))]);
+
+        let expected = Value::List(vec![
+            obj(None, vec![("name", text("oat milk")), ("id", text("1"))])
+        ]);
+
+        assert_eq!(store(&s, vec![]), format!("{:?}", expected));
+        assert_eq!(store(&s, vec![("id", text("2"))]), format!("{:?}", Value::None));
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use flexscript::Obj;
+use flexscript::ObjProp;
+use flexscript::Value;
+
+pub fn obj(name: Option<&str>, props: Vec<(&str, Value)>) -> Value {
+    Value::Obj(Obj {
+        name: name.map(|n| n.to_string()),
+        props: props.into_iter()
+            .map(|(name, value)| ObjProp {
+                name: name.to_string(),
+                value: value
+            })
+            .collect()
+    })
+}
+
+pub fn str_map(map: &HashMap<String, String>) -> Value {
+    let mut keys = map.keys().collect::<Vec<&String>>();
+    keys.sort();
+
+    obj(
+        None,
+        keys.into_iter()
+            .map(|k| (k.as_str(), Value::Str(map[k].clone())))
+            .collect()
+    )
+}
+
+/// Form fields sent once are strings, fields sent several times are lists
+/// of strings.
+pub fn fields_map(fields: &HashMap<String, Vec<String>>) -> Value {
+    let mut keys = fields.keys().collect::<Vec<&String>>();
+    keys.sort();
+
+    obj(
+        None,
+        keys.into_iter()
+            .map(|k| {
+                let value = match fields[k].as_slice() {
+                    [value] => Value::Str(value.clone()),
+                    values => Value::List(values.iter().cloned().map(Value::Str).collect())
+                };
+
+                (k.as_str(), value)
+            })
+            .collect()
+    )
+}
+
+/// Converts a value to JSON. Values with no JSON counterpart, such as
+/// functions, become null.
+pub fn to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::None => serde_json::Value::Null,
+        Value::Bool(b) => serde_json::Value::Bool(*b),
+        Value::Int(i) => serde_json::Value::from(*i),
+        Value::Float(f) => serde_json::Value::from(*f),
+        Value::Str(s) => serde_json::Value::String(s.clone()),
+        Value::List(list) => serde_json::Value::Array(
+            list.iter().map(to_json).collect()
+        ),
+        Value::Obj(obj) => serde_json::Value::Object(
+            obj.props.iter()
+                .map(|p| (p.name.clone(), to_json(&p.value)))
+                .collect()
+        ),
+        _ => serde_json::Value::Null
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_to_json() {
+        let value = obj(Some("Todo"), vec![
+            ("name", Value::Str("milk".to_string())),
+            ("done", Value::Bool(false)),
+            ("tags", Value::List(vec![Value::Int(1), Value::Float(1.5)])),
+            ("parent", Value::None)
+        ]);
+
+        assert_eq!(
+            to_json(&value),
+            serde_json::json!({
+                "name": "milk",
+                "done": false,
+                "tags": [1, 1.5],
+                "parent": null
+            })
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::panic::AssertUnwindSafe;
+use std::panic::catch_unwind;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use flexscript::RunResult;
+use flexscript::Value;
+use flexscript::Vm;
+use futures::FutureExt;
+use tokio::sync::Semaphore;
+
+use crate::errors::panic_message;
+use crate::resolvers::Resolvers;
+
+/// Vm with every route block compiled into it. Block ids are only valid
+/// inside the Vm that compiled them, so each instance keeps its own list
+/// indexed by route. A route that failed to compile keeps its error.
+pub struct PooledVm {
+    pub vm: Vm,
+    pub blks: Vec<Result<u32, String>>
+}
+
+impl PooledVm {
+    pub fn new(codes: &[String]) -> Self {
+        let mut vm = Vm::new();
+        let blks = codes.iter()
+            .map(|code| {
+                catch_unwind(AssertUnwindSafe(|| vm.compile_code(code)))
+                    .map_err(panic_message)
+            })
+            .collect();
+
+        Self {
+            vm: vm,
+            blks: blks
+        }
+    }
+}
+
+pub struct VmPool {
+    codes: Vec<String>,
+    vms: Mutex<Vec<PooledVm>>,
+    available: Semaphore
+}
+
+impl VmPool {
+    pub fn new(size: usize, codes: Vec<String>) -> Self {
+        let size = size.max(1);
+        let vms = (0..size)
+            .map(|_| PooledVm::new(&codes))
+            .collect();
+
+        Self {
+            codes: codes,
+            vms: Mutex::new(vms),
+            available: Semaphore::new(size)
+        }
+    }
+
+    /// Compile errors by route index.
+    pub fn compile_errors(&self) -> Vec<(usize, String)> {
+        match self.vms.lock().unwrap().first() {
+            Some(pooled) => pooled.blks.iter()
+                .enumerate()
+                .filter_map(|(inx, blk)| blk.as_ref().err().map(|err| (inx, err.clone())))
+                .collect(),
+            None => vec![]
+        }
+    }
+
+    /// Runs route block `route` on the first free Vm. The Vm is moved to a
+    /// blocking thread for the duration of the run so a slow page does not
+    /// stall the runtime. When the page awaits, the Vm stays checked out
+    /// while the value is resolved and the suspended stack is resumed.
+    ///
+    /// A panic inside the Vm is returned as an error and the Vm is replaced
+    /// with a fresh one, since its state can no longer be trusted.
+    pub async fn run(&self, route: usize, args: Value, resolvers: &Resolvers) -> anyhow::Result<RunResult> {
+        let _permit = self.available.acquire().await.unwrap();
+        let mut pooled = self.vms.lock().unwrap().pop()
+            .unwrap_or_else(|| PooledVm::new(&self.codes));
+
+        let blk = match &pooled.blks[route] {
+            Ok(blk) => *blk,
+            Err(err) => {
+                let err = anyhow!("compile error: {}", err);
+                self.vms.lock().unwrap().push(pooled);
+
+                return Err(err);
+            }
+        };
+
+        let mut step = Step::Run(blk, args);
+
+        let res = loop {
+            let join = tokio::task::spawn_blocking(move || {
+                let mut pooled = pooled;
+                let res = match step {
+                    Step::Run(blk, args) => {
+                        pooled.vm.run_blk(blk, args)
+                    },
+                    Step::Resume(stack_id, value) => {
+                        pooled.vm.resume(stack_id, value)
+                    }
+                };
+
+                (pooled, res)
+            }).await;
+
+            let (p, res) = match join {
+                Ok(r) => r,
+                Err(err) => {
+                    self.vms.lock().unwrap().push(PooledVm::new(&self.codes));
+
+                    return Err(match err.try_into_panic() {
+                        Ok(payload) => anyhow!(panic_message(payload)),
+                        Err(err) => anyhow!(err)
+                    });
+                }
+            };
+
+            pooled = p;
+
+            match res {
+                RunResult::Await { stack_id, value } => {
+                    log::debug!("stack {} awaiting {:?}", stack_id, value);
+
+                    let resolved = AssertUnwindSafe(resolvers.resolve(value))
+                        .catch_unwind()
+                        .await;
+
+                    match resolved {
+                        Ok(value) => step = Step::Resume(stack_id, value),
+                        Err(payload) => {
+                            self.vms.lock().unwrap().push(PooledVm::new(&self.codes));
+
+                            return Err(anyhow!(panic_message(payload)));
+                        }
+                    }
+                },
+                res => break res
+            }
+        };
+
+        self.vms.lock().unwrap().push(pooled);
+
+        Ok(res)
+    }
+}
+
+enum Step {
+    Run(u32, Value),
+    Resume(usize, Value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn runs_routes_concurrently() {
+        let pool = VmPool::new(2, vec![
+            "return 1".to_string(),
+            "return 2".to_string()
+        ]);
+
+        let resolvers = Resolvers::new();
+
+        let res = tokio::join!(
+            pool.run(0, Value::None, &resolvers),
+            pool.run(1, Value::None, &resolvers),
+            pool.run(0, Value::None, &resolvers)
+        );
+
+        match res {
+            (
+                Ok(RunResult::Value(Value::Int(1))),
+                Ok(RunResult::Value(Value::Int(2))),
+                Ok(RunResult::Value(Value::Int(1)))
+            ) => {},
+            res => panic!("unexpected {:?}", res)
+        }
+    }
+}
+